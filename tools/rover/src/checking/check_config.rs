@@ -0,0 +1,34 @@
+use crate::repository::{diagnostic::Severity, repo::Repository};
+use anyhow::{bail, Result};
+use std::path::Path;
+
+pub fn check_config(path: &Path, all: bool) -> Result<()> {
+    let repository = Repository::from_path(path)?;
+    let problems = if all {
+        repository.problems(None)?
+    } else {
+        vec![repository.problem_from_path(path)?]
+    };
+    let (mut errors, mut warnings) = (0, 0);
+    for problem in &problems {
+        let config_path = problem.config_path();
+        let relative_path = config_path
+            .strip_prefix(repository.get_path())
+            .unwrap_or(&config_path);
+        for diagnostic in problem.check_config()? {
+            match diagnostic.severity() {
+                Severity::Error => errors += 1,
+                Severity::Warning => warnings += 1,
+            }
+            println!("{}:{diagnostic}", relative_path.display());
+        }
+    }
+    println!(
+        "Checked {} config(s): {errors} error(s), {warnings} warning(s)",
+        problems.len()
+    );
+    if errors > 0 {
+        bail!("config check failed")
+    }
+    Ok(())
+}
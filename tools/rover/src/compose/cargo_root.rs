@@ -1,48 +1,67 @@
 use super::config::Config;
 use anyhow::{Context, Result};
 use std::{
-    fs,
+    fs, io,
     path::{Path, PathBuf},
 };
+use toml_edit::{Array, DocumentMut, Item, Table, Value};
 
-pub fn cargo_root(output: &Path, config: &Config) -> Result<()> {
-    let problems: Vec<_> = config
-        .get_problems()
-        .iter()
-        .map(|path| {
-            let path = PathBuf::from("problems").join(path);
-            path.to_str().unwrap().to_string()
-        })
-        .collect();
-    let tools: Vec<_> = config
-        .get_tools()
-        .iter()
-        .map(|path| {
-            let path = PathBuf::from("tools").join(path);
-            path.to_str().unwrap().to_string()
-        })
-        .collect();
-    let add_to_toml: Vec<_> = config
-        .get_add_to_toml()
-        .iter()
-        .map(|path| path.to_str().unwrap().to_string())
-        .collect();
-    let content = format!(
-        r#"[workspace]
-members = [
-    # Problems
-    "{}",
-
-    # Tools
-    "{}",
-
-    # Additional
-    "{}",
-]
-"#,
-        problems.join("\",\n    \""),
-        tools.join("\",\n    \""),
-        add_to_toml.join("\",\n    \"")
-    );
-    fs::write(output.join("Cargo.toml"), content).context("failed to write Cargo.toml")
+// Takes the root manifest of the private repository and replaces only its workspace members with
+// the composed ones, so the other tables, keys and comments are kept.
+pub fn cargo_root(input: &Path, config: &Config) -> Result<String> {
+    let path = input.join("Cargo.toml");
+    let mut document = match fs::read_to_string(&path) {
+        Ok(content) => content
+            .parse::<DocumentMut>()
+            .with_context(|| format!("failed to parse {path:?}"))?,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => DocumentMut::new(),
+        Err(err) => return Err(err).with_context(|| format!("failed to read {path:?}")),
+    };
+    let groups = [
+        (
+            "Problems",
+            config
+                .get_problems()
+                .iter()
+                .map(|path| PathBuf::from("problems").join(path))
+                .collect::<Vec<_>>(),
+        ),
+        (
+            "Tools",
+            config
+                .get_tools()
+                .iter()
+                .map(|path| PathBuf::from("tools").join(path))
+                .collect(),
+        ),
+        ("Additional", config.get_add_to_toml().to_vec()),
+    ];
+    let mut members = Array::new();
+    for (name, paths) in groups {
+        for (i, path) in paths.iter().enumerate() {
+            let prefix = match (i, members.is_empty()) {
+                (0, true) => format!("\n    # {name}\n    "),
+                (0, false) => format!("\n\n    # {name}\n    "),
+                _ => "\n    ".to_string(),
+            };
+            let path = path.to_str().context("member path is not valid UTF-8")?;
+            members.push_formatted(Value::from(path).decorated(prefix, ""));
+        }
+    }
+    if !members.is_empty() {
+        members.set_trailing_comma(true);
+        members.set_trailing("\n");
+    }
+    let workspace = document
+        .entry("workspace")
+        .or_insert_with(|| Item::Table(Table::new()))
+        .as_table_like_mut()
+        .with_context(|| format!("workspace in {path:?} is not a table"))?;
+    match workspace.get_mut("members") {
+        Some(item) => *item = Item::Value(Value::Array(members)),
+        None => {
+            workspace.insert("members", Item::Value(Value::Array(members)));
+        }
+    }
+    Ok(document.to_string())
 }
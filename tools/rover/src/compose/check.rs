@@ -0,0 +1,61 @@
+use super::session::Session;
+use anyhow::{bail, Context, Result};
+use std::{
+    io::{BufRead, BufReader},
+    path::Path,
+    process::{Command, Stdio},
+};
+
+// Runs `cargo check` on the composed workspace and points every error to the private source. The
+// build directory is kept in the private repository so the output stays clean.
+pub fn check(input: &Path, output: &Path, session: &Session) -> Result<()> {
+    let mut child = Command::new("cargo")
+        .args([
+            "check",
+            "--workspace",
+            "--all-targets",
+            "--message-format",
+            "json",
+        ])
+        .current_dir(output)
+        .env(
+            "CARGO_TARGET_DIR",
+            input.join("target").join("compose-check"),
+        )
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .context("failed to launch cargo check")?;
+    let stdout = child.stdout.take().context("no stdout of cargo check")?;
+    for line in BufReader::new(stdout).lines() {
+        let line = line.context("failed to read output of cargo check")?;
+        let Ok(event) = serde_json::from_str::<serde_json::Value>(&line) else {
+            continue;
+        };
+        let message = &event["message"];
+        if event["reason"] != "compiler-message" || message["level"] != "error" {
+            continue;
+        }
+        if let Some(rendered) = message["rendered"].as_str() {
+            eprint!("{rendered}");
+        }
+        let spans = message["spans"].as_array().into_iter().flatten();
+        for span in spans.filter(|span| span["is_primary"] == true) {
+            let (Some(file), Some(line)) =
+                (span["file_name"].as_str(), span["line_start"].as_u64())
+            else {
+                continue;
+            };
+            let file = Path::new(file);
+            let file = file.strip_prefix(output).unwrap_or(file);
+            if let Some((private, line)) = session.locate(file, line as usize) {
+                eprintln!("  = private source: {}:{line}\n", private.display());
+            }
+        }
+    }
+    let status = child.wait().context("failed to wait for cargo check")?;
+    if !status.success() {
+        bail!("composed output does not compile");
+    }
+    Ok(())
+}
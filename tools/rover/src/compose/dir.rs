@@ -1,8 +1,8 @@
-use super::file::process_file;
+use super::{file::process_file, session::Session};
 use anyhow::{Context, Result};
 use std::{fs, path::Path};
 
-pub fn process_dir(input: &Path, output: &Path) -> Result<()> {
+pub fn process_dir(input: &Path, output: &Path, session: &mut Session) -> Result<()> {
     let dir = fs::read_dir(input).with_context(|| format!("failed to read directory {input:?}"))?;
     for entry in dir {
         let input = entry
@@ -10,9 +10,9 @@ pub fn process_dir(input: &Path, output: &Path) -> Result<()> {
             .path();
         let output = output.join(input.file_name().unwrap());
         if input.is_dir() {
-            process_dir(&input, &output)?;
+            process_dir(&input, &output, session)?;
         } else {
-            process_file(&input, &output)?;
+            process_file(&input, &output, session)?;
         }
     }
     Ok(())
@@ -1,71 +1,15 @@
+use super::{
+    session::Session,
+    token::{parse_token, Token, TokenKind},
+};
 use anyhow::{bail, Context, Result};
+use chrono::NaiveDate;
 use std::{fs, path::Path};
 
-#[allow(clippy::enum_variant_names)]
-enum TokenKind {
-    Private,
-    BeginPrivate,
-    EndPrivate,
-}
-
-#[derive(Clone, Copy, PartialEq, Eq)]
-enum TokenProperty {
-    NoHint,
-    Unimplemented,
-}
-
-struct Token {
-    kind: TokenKind,
-    properties: Vec<TokenProperty>,
-}
-
-fn parse_token(line: &str) -> Result<Option<Token>> {
-    let comment = match line.find("//") {
-        Some(pos) => &line[pos..],
-        None => return Ok(None),
-    };
-
-    let cmd = match comment.find("compose::") {
-        Some(pos) => &comment[pos + "compose::".len()..],
-        None => return Ok(None),
-    };
-
-    let kind = if cmd.starts_with("private") {
-        TokenKind::Private
-    } else if cmd.starts_with("begin_private") {
-        TokenKind::BeginPrivate
-    } else if cmd.starts_with("end_private") {
-        TokenKind::EndPrivate
-    } else {
-        bail!("unknown compose command: {}", cmd);
-    };
-
-    let properties_str = match cmd.find('(') {
-        Some(pos) => {
-            if !cmd.trim_end().ends_with(')') {
-                bail!("unclosed '('");
-            }
-            &cmd[pos + 1..cmd.trim_end().len() - 1]
-        }
-        None => "",
-    };
-
-    let mut properties = vec![];
-    for prop in properties_str.split_inclusive(',') {
-        match prop {
-            "no_hint" => properties.push(TokenProperty::NoHint),
-            "unimplemented" => properties.push(TokenProperty::Unimplemented),
-            s => bail!("unknown property: {}", s),
-        }
-    }
-
-    Ok(Some(Token { kind, properties }))
-}
-
 fn find_token(lines: &[&str], start: usize) -> Result<Option<(usize, Token)>> {
     for (i, line) in lines[start..].iter().enumerate() {
         let mb_token = parse_token(line)
-            .with_context(|| format!("failed to parse token on line {}", i + 1))?;
+            .with_context(|| format!("failed to parse token on line {}", i + start + 1))?;
         if let Some(token) = mb_token {
             return Ok(Some((i + start, token)));
         }
@@ -73,13 +17,19 @@ fn find_token(lines: &[&str], start: usize) -> Result<Option<(usize, Token)>> {
     Ok(None)
 }
 
-fn process_source(src: String) -> Result<String> {
+// Also returns the 1-based line of the source for every line of the result. Inserted lines point
+// to the line of their directive.
+fn process_source(src: &str, today: NaiveDate) -> Result<(String, Vec<usize>)> {
     let mut dst = String::new();
+    let mut source_lines = vec![];
 
     let lines = src.lines().collect::<Vec<_>>();
     let mut next_pos = 0;
+    // Closing lines of the blocks that are already public.
+    let mut public_ends = vec![];
     while let Some((begin, token)) = find_token(&lines, next_pos)? {
         let end = match token.kind {
+            TokenKind::EndPrivate if public_ends.contains(&begin) => begin + 1,
             TokenKind::EndPrivate => bail!("unpaired 'end_private' on line {}", begin + 1),
             TokenKind::Private => begin + 1,
             TokenKind::BeginPrivate => {
@@ -108,11 +58,28 @@ fn process_source(src: String) -> Result<String> {
         for i in next_pos..begin {
             dst += lines[i];
             dst += "\n";
+            source_lines.push(i + 1);
         }
 
-        let no_hint = token.properties.contains(&TokenProperty::NoHint);
-        let unimpl = token.properties.contains(&TokenProperty::Unimplemented);
-        if no_hint {
+        let properties = &token.properties;
+        if matches!(token.kind, TokenKind::EndPrivate) {
+            next_pos = end;
+        } else if properties.public_after.is_some_and(|date| date <= today) {
+            // The directives are dropped and the code is kept. The lines of a block are scanned
+            // again for the directives inside.
+            match token.kind {
+                TokenKind::BeginPrivate => public_ends.push(end - 1),
+                _ => {
+                    let line = &lines[begin][..token.comment];
+                    if !line.trim().is_empty() {
+                        dst += line.trim_end();
+                        dst += "\n";
+                        source_lines.push(begin + 1);
+                    }
+                }
+            }
+            next_pos = begin + 1;
+        } else if properties.no_hint && properties.replace.is_none() {
             if begin > 0
                 && lines[begin - 1].trim().is_empty()
                 && end < lines.len()
@@ -132,37 +99,53 @@ fn process_source(src: String) -> Result<String> {
                     }
                 }
                 dst.push_str(line);
+                dst.push('\n');
+                source_lines.push(begin + 1);
             };
 
-            insert_line("// TODO: your code goes here.\n");
-            if unimpl {
-                insert_line("unimplemented!()\n");
+            if !properties.no_hint {
+                let hint = properties.hint.as_deref().unwrap_or("your code goes here.");
+                for (i, line) in hint.lines().enumerate() {
+                    match i {
+                        0 => insert_line(&format!("// TODO: {line}")),
+                        _ => insert_line(&format!("// {line}")),
+                    }
+                }
+            }
+            if let Some(replace) = &properties.replace {
+                for line in replace.lines() {
+                    insert_line(line);
+                }
             }
 
             next_pos = end;
         }
     }
 
-    for line in &lines[next_pos..] {
+    for (i, line) in lines.iter().enumerate().skip(next_pos) {
         dst += line;
         dst += "\n";
+        source_lines.push(i + 1);
     }
 
-    Ok(dst)
+    Ok((dst, source_lines))
 }
 
-pub fn process_file(input: &Path, output: &Path) -> Result<()> {
-    let out_dir = output.parent().unwrap();
-    fs::create_dir_all(out_dir).context("failed to create directory")?;
-    if input.to_str().map(|s| s.ends_with(".rs")).unwrap_or(false) {
-        let content = fs::read_to_string(input)
-            .with_context(|| format!("failed to read file {:?}", input))?;
-        let new_content = process_source(content)
-            .with_context(|| format!("failed to process file {:?}", input))?;
-        fs::write(output, new_content).with_context(|| format!("failed to write file {:?}", input))
-    } else {
-        fs::copy(input, output)
-            .map(|_| ())
-            .with_context(|| format!("failed to copy {:?} to {:?}", input, output))
+pub fn process_file(input: &Path, output: &Path, session: &mut Session) -> Result<()> {
+    let permissions = fs::metadata(input)
+        .with_context(|| format!("failed to read metadata of {:?}", input))?
+        .permissions();
+    if !input.to_str().map(|s| s.ends_with(".rs")).unwrap_or(false) {
+        let content =
+            fs::read(input).with_context(|| format!("failed to read file {:?}", input))?;
+        session.add_file(output, content, Some(permissions));
+        return Ok(());
     }
+    let content =
+        fs::read_to_string(input).with_context(|| format!("failed to read file {:?}", input))?;
+    let (new_content, source_lines) = process_source(&content, session.get_today())
+        .with_context(|| format!("failed to process file {:?}", input))?;
+    session.add_source_map(input, output, &content, &new_content, source_lines);
+    session.add_file(output, new_content.into_bytes(), Some(permissions));
+    Ok(())
 }
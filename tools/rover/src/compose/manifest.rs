@@ -0,0 +1,41 @@
+use super::{config::Config, session::Session};
+use crate::repository::{
+    integrity::{is_build_output, Manifest, MANIFEST_NAME},
+    repo::{Repository, PROBLEMS_FOLDER},
+};
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+// Adds the integrity manifest of the composed files to every problem with a config. The files
+// matched by `allowed-patterns` in the private repository and build outputs are left out.
+pub fn add_manifests(
+    repository: &Repository,
+    config: &Config,
+    session: &mut Session,
+) -> Result<()> {
+    for problem in config.get_problems() {
+        let output = PathBuf::from(PROBLEMS_FOLDER).join(problem);
+        let problem = repository.problem_from_path(&repository.get_path().join(&output))?;
+        if !problem.config_path().is_file() {
+            continue;
+        }
+        let problem_config = problem.config()?;
+        let allowed = problem_config.get_relative_user_files();
+        let mut manifest = Manifest::default();
+        for (path, file) in session
+            .get_files()
+            .range(output.clone()..)
+            .take_while(|(path, _)| path.starts_with(&output))
+        {
+            let relative = path.strip_prefix(&output)?;
+            if relative != Path::new(MANIFEST_NAME)
+                && !is_build_output(relative)
+                && !allowed.iter().any(|file| file == relative)
+            {
+                manifest.add(relative, file.get_content());
+            }
+        }
+        session.add_file(&output.join(MANIFEST_NAME), manifest.to_json()?, None);
+    }
+    Ok(())
+}
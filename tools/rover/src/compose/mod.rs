@@ -1,8 +1,11 @@
 mod cargo_root;
+mod check;
 pub mod config;
 mod dir;
 mod file;
+mod manifest;
 mod process;
-mod prune;
 pub mod run_compose;
-mod skip;
+mod session;
+mod sync;
+mod token;
@@ -1,8 +1,8 @@
-use super::{config::Config, dir::process_dir, file::process_file};
+use super::{config::Config, dir::process_dir, file::process_file, session::Session};
 use anyhow::Result;
 use std::path::{Path, PathBuf};
 
-pub fn process(input: &Path, output: &Path, config: &Config) -> Result<()> {
+pub fn process(input: &Path, config: &Config, session: &mut Session) -> Result<()> {
     let to_process = std::iter::empty()
         .chain(
             config
@@ -19,11 +19,10 @@ pub fn process(input: &Path, output: &Path, config: &Config) -> Result<()> {
         .chain(config.get_copy().iter().cloned());
     for entry in to_process {
         let input = input.join(&entry);
-        let output = output.join(&entry);
         if input.is_dir() {
-            process_dir(&input, &output)?;
+            process_dir(&input, &entry, session)?;
         } else {
-            process_file(&input, &output)?;
+            process_file(&input, &entry, session)?;
         }
     }
     Ok(())
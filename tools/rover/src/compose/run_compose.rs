@@ -1,9 +1,18 @@
-use super::{cargo_root::cargo_root, process::process, prune::prune, skip::skip};
+use super::{
+    cargo_root::cargo_root, check::check, manifest::add_manifests, process::process,
+    session::Session, sync::sync,
+};
 use crate::repository::repo::Repository;
 use anyhow::{Context, Result};
+use chrono::NaiveDate;
 use std::path::Path;
 
-pub fn run_compose(input: &Path, output: &Path) -> Result<()> {
+pub fn run_compose(
+    input: &Path,
+    output: &Path,
+    today: NaiveDate,
+    check_output: bool,
+) -> Result<()> {
     let repository = Repository::from_path(input)?;
     let config = repository.compose_config()?;
     let input = repository.get_path().to_path_buf();
@@ -17,8 +26,28 @@ pub fn run_compose(input: &Path, output: &Path) -> Result<()> {
                 .file_name()
                 .context("output path has no file name to canonicalize")?,
         );
-    prune(&output, &config)?;
-    process(&input, &output, &config)?;
-    cargo_root(&output, &config)?;
-    skip(&output, &config)
+    let mut session = Session::new(&input, false, today, config.get_skipped());
+    process(&input, &config, &mut session)?;
+    add_manifests(&repository, &config, &mut session)?;
+    session.add_file(
+        Path::new("Cargo.toml"),
+        cargo_root(&input, &config)?.into_bytes(),
+        None,
+    );
+    sync(&output, &config, &session)?;
+    if check_output {
+        check(&input, &output, &session)?;
+    }
+    Ok(())
+}
+
+// Prints the changes made to the private sources without writing anything.
+pub fn dry_run_compose(input: &Path, today: NaiveDate) -> Result<()> {
+    let repository = Repository::from_path(input)?;
+    let config = repository.compose_config()?;
+    let input = repository.get_path().to_path_buf();
+    let mut session = Session::new(&input, true, today, config.get_skipped());
+    process(&input, &config, &mut session)?;
+    eprintln!("{} file(s) would be changed", session.get_changed());
+    Ok(())
 }
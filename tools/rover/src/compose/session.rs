@@ -0,0 +1,138 @@
+use chrono::NaiveDate;
+use similar::TextDiff;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::Permissions,
+    ops::Bound,
+    path::{Path, PathBuf},
+};
+
+// The private file and line of every line of a composed file.
+struct SourceMap {
+    input: PathBuf,
+    lines: Vec<usize>,
+}
+
+pub struct ComposedFile {
+    content: Vec<u8>,
+    permissions: Option<Permissions>,
+}
+
+impl ComposedFile {
+    pub fn get_content(&self) -> &[u8] {
+        &self.content
+    }
+
+    pub fn get_permissions(&self) -> Option<&Permissions> {
+        self.permissions.as_ref()
+    }
+}
+
+// The composed output in memory. Paths of the output are relative to its root, and the entries
+// from `skip-entries` are never added.
+pub struct Session {
+    input_root: PathBuf,
+    dry_run: bool,
+    today: NaiveDate,
+    skipped: Vec<PathBuf>,
+    changed: usize,
+    files: BTreeMap<PathBuf, ComposedFile>,
+    source_maps: HashMap<PathBuf, SourceMap>,
+}
+
+impl Session {
+    pub fn new(input_root: &Path, dry_run: bool, today: NaiveDate, skipped: &[PathBuf]) -> Self {
+        Self {
+            input_root: input_root.to_path_buf(),
+            dry_run,
+            today,
+            skipped: skipped.to_vec(),
+            changed: 0,
+            files: BTreeMap::new(),
+            source_maps: HashMap::new(),
+        }
+    }
+
+    // The date to decide which solutions are already public.
+    pub fn get_today(&self) -> NaiveDate {
+        self.today
+    }
+
+    pub fn get_changed(&self) -> usize {
+        self.changed
+    }
+
+    pub fn get_files(&self) -> &BTreeMap<PathBuf, ComposedFile> {
+        &self.files
+    }
+
+    fn is_skipped(&self, output: &Path) -> bool {
+        self.skipped.iter().any(|entry| output.starts_with(entry))
+    }
+
+    pub fn has_file(&self, output: &Path) -> bool {
+        self.files.contains_key(output)
+    }
+
+    // Paths are ordered by components, so the files inside a directory follow it.
+    pub fn has_dir(&self, output: &Path) -> bool {
+        self.files
+            .range::<Path, _>((Bound::Excluded(output), Bound::Unbounded))
+            .next()
+            .is_some_and(|(path, _)| path.starts_with(output))
+    }
+
+    pub fn add_file(&mut self, output: &Path, content: Vec<u8>, permissions: Option<Permissions>) {
+        if self.dry_run || self.is_skipped(output) {
+            return;
+        }
+        self.files.insert(
+            output.to_path_buf(),
+            ComposedFile {
+                content,
+                permissions,
+            },
+        );
+    }
+
+    // Prints the unified diff between the private and the composed source in dry run.
+    pub fn add_source_map(
+        &mut self,
+        input: &Path,
+        output: &Path,
+        private: &str,
+        composed: &str,
+        lines: Vec<usize>,
+    ) {
+        if self.is_skipped(output) {
+            return;
+        }
+        let input = input.strip_prefix(&self.input_root).unwrap_or(input);
+        if self.dry_run && private != composed {
+            let diff = TextDiff::from_lines(private, composed);
+            print!(
+                "{}",
+                diff.unified_diff().header(
+                    &format!("a/{}", input.display()),
+                    &format!("b/{}", output.display())
+                )
+            );
+            self.changed += 1;
+        }
+        self.source_maps.insert(
+            output.to_path_buf(),
+            SourceMap {
+                input: input.to_path_buf(),
+                lines,
+            },
+        );
+    }
+
+    // Maps the 1-based line of a composed file to the private file, relative to the private
+    // repository, and the line there.
+    pub fn locate(&self, output: &Path, line: usize) -> Option<(&Path, usize)> {
+        let map = self.source_maps.get(output)?;
+        let line = *map.lines.get(line.checked_sub(1)?)?;
+        Some((&map.input, line))
+    }
+}
@@ -0,0 +1,94 @@
+use super::{config::Config, session::Session};
+use anyhow::{Context, Result};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+#[derive(Default)]
+struct Summary {
+    created: Vec<PathBuf>,
+    updated: Vec<PathBuf>,
+    removed: Vec<PathBuf>,
+    unchanged: usize,
+}
+
+// Brings the output to the composed state: only the files with changed bytes are written, and
+// only the paths that are not composed anymore are removed, except for `do-not-delete` ones.
+pub fn sync(output: &Path, config: &Config, session: &Session) -> Result<()> {
+    let mut summary = Summary::default();
+    if output.is_dir() {
+        remove_stale(output, Path::new(""), config, session, &mut summary)?;
+    }
+    for (relative, file) in session.get_files() {
+        let path = output.join(relative);
+        match fs::read(&path) {
+            Ok(content) if content == file.get_content() => {
+                summary.unchanged += 1;
+                continue;
+            }
+            Ok(_) => summary.updated.push(relative.clone()),
+            Err(_) => {
+                fs::create_dir_all(path.parent().unwrap())
+                    .with_context(|| format!("failed to create directory for {path:?}"))?;
+                summary.created.push(relative.clone());
+            }
+        }
+        fs::write(&path, file.get_content())
+            .with_context(|| format!("failed to write file {path:?}"))?;
+        if let Some(permissions) = file.get_permissions() {
+            fs::set_permissions(&path, permissions.clone())
+                .with_context(|| format!("failed to set permissions of {path:?}"))?;
+        }
+    }
+    summary.removed.sort();
+    for (action, paths) in [
+        ("created", &summary.created),
+        ("updated", &summary.updated),
+        ("removed", &summary.removed),
+    ] {
+        for path in paths {
+            println!("{action} {}", path.display());
+        }
+    }
+    println!(
+        "{} created, {} updated, {} removed, {} unchanged",
+        summary.created.len(),
+        summary.updated.len(),
+        summary.removed.len(),
+        summary.unchanged
+    );
+    Ok(())
+}
+
+fn remove_stale(
+    dir: &Path,
+    relative: &Path,
+    config: &Config,
+    session: &Session,
+    summary: &mut Summary,
+) -> Result<()> {
+    let entries = fs::read_dir(dir).with_context(|| format!("failed to read directory {dir:?}"))?;
+    for entry in entries {
+        let entry = entry.with_context(|| format!("failed to read entry in directory {dir:?}"))?;
+        let relative = relative.join(entry.file_name());
+        if config.get_do_not_delete().contains(&relative) {
+            continue;
+        }
+        let path = entry.path();
+        let is_dir = entry
+            .file_type()
+            .with_context(|| format!("failed to read file type of {path:?}"))?
+            .is_dir();
+        if is_dir && session.has_dir(&relative) {
+            remove_stale(&path, &relative, config, session, summary)?;
+        } else if is_dir {
+            fs::remove_dir_all(&path).with_context(|| format!("failed to remove {path:?}"))?;
+            summary.removed.push(relative);
+        } else if !session.has_file(&relative) {
+            fs::remove_file(&path).with_context(|| format!("failed to remove {path:?}"))?;
+            summary.removed.push(relative);
+        }
+    }
+    Ok(())
+}
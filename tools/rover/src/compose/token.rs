@@ -0,0 +1,205 @@
+use anyhow::{bail, Context, Result};
+use chrono::NaiveDate;
+
+const PREFIX: &str = "compose::";
+
+#[allow(clippy::enum_variant_names)]
+pub enum TokenKind {
+    Private,
+    BeginPrivate,
+    EndPrivate,
+}
+
+#[derive(Default)]
+pub struct Properties {
+    pub no_hint: bool,
+    pub hint: Option<String>,
+    pub replace: Option<String>,
+    pub public_after: Option<NaiveDate>,
+}
+
+pub struct Token {
+    pub kind: TokenKind,
+    pub properties: Properties,
+    // Byte offset of the comment with the directive in the line.
+    pub comment: usize,
+}
+
+// The grammar of a directive in a comment:
+//
+//   directive = kind [ "(" [ property { "," property } ] ")" ]
+//             | shorthand "(" string ")"
+//   kind      = "private" | "begin_private" | "end_private"
+//   shorthand = "replace" | "hint" | "public_after"
+//   property  = "no_hint" | "unimplemented" | shorthand "(" string ")"
+//
+// Strings are double-quoted with \" \\ \n and \t escapes. A shorthand is a `private` directive
+// with the single property.
+pub fn parse_token(line: &str) -> Result<Option<Token>> {
+    // The comment is the last one opened before the prefix, the code may contain "//" in strings.
+    let Some((comment, directive)) = line.match_indices(PREFIX).find_map(|(pos, _)| {
+        let comment = line[..pos].rfind("//")?;
+        Some((comment, &line[pos + PREFIX.len()..]))
+    }) else {
+        return Ok(None);
+    };
+
+    let mut cursor = Cursor { rest: directive };
+    let name = cursor.ident()?;
+    let kind = match name {
+        "private" => TokenKind::Private,
+        "begin_private" => TokenKind::BeginPrivate,
+        "end_private" => TokenKind::EndPrivate,
+        "replace" | "hint" | "public_after" => {
+            let mut properties = Properties::default();
+            properties.set(name, &mut cursor)?;
+            cursor.finish()?;
+            return Ok(Some(Token {
+                kind: TokenKind::Private,
+                properties,
+                comment,
+            }));
+        }
+        name => bail!("unknown compose command: {name}"),
+    };
+
+    let mut properties = Properties::default();
+    if cursor.eat('(') && !cursor.eat(')') {
+        loop {
+            let name = cursor.ident()?;
+            properties.set(name, &mut cursor)?;
+            if cursor.eat(')') {
+                break;
+            }
+            cursor.expect(',')?;
+        }
+    }
+    cursor.finish()?;
+    if matches!(kind, TokenKind::EndPrivate) && !properties.is_empty() {
+        bail!("'end_private' takes no properties");
+    }
+    Ok(Some(Token {
+        kind,
+        properties,
+        comment,
+    }))
+}
+
+impl Properties {
+    fn is_empty(&self) -> bool {
+        !self.no_hint
+            && self.hint.is_none()
+            && self.replace.is_none()
+            && self.public_after.is_none()
+    }
+
+    fn set(&mut self, name: &str, cursor: &mut Cursor) -> Result<()> {
+        let duplicate = match name {
+            "no_hint" => std::mem::replace(&mut self.no_hint, true),
+            "unimplemented" => self
+                .replace
+                .replace("unimplemented!()".to_string())
+                .is_some(),
+            "replace" => self.replace.replace(cursor.argument()?).is_some(),
+            "hint" => self.hint.replace(cursor.argument()?).is_some(),
+            "public_after" => {
+                let date = cursor.argument()?;
+                let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+                    .with_context(|| format!("invalid date \"{date}\", expected YYYY-MM-DD"))?;
+                self.public_after.replace(date).is_some()
+            }
+            name => bail!("unknown property: {name}"),
+        };
+        if duplicate {
+            bail!("property '{name}' conflicts with a previous one");
+        }
+        if self.no_hint && self.hint.is_some() {
+            bail!("properties 'no_hint' and 'hint' conflict");
+        }
+        Ok(())
+    }
+}
+
+struct Cursor<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Cursor<'a> {
+    fn found(&self) -> String {
+        match self.rest.chars().next() {
+            Some(c) => format!("'{c}'"),
+            None => "end of line".to_string(),
+        }
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.rest = self.rest.trim_start();
+        match self.rest.strip_prefix(c) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        if !self.eat(c) {
+            bail!("expected '{c}', found {}", self.found());
+        }
+        Ok(())
+    }
+
+    fn ident(&mut self) -> Result<&'a str> {
+        self.rest = self.rest.trim_start();
+        let len = self
+            .rest
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .unwrap_or(self.rest.len());
+        if len == 0 {
+            bail!("expected a name, found {}", self.found());
+        }
+        let (ident, rest) = self.rest.split_at(len);
+        self.rest = rest;
+        Ok(ident)
+    }
+
+    fn string(&mut self) -> Result<String> {
+        self.expect('"')?;
+        let mut value = String::new();
+        let mut chars = self.rest.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.rest = &self.rest[i + 1..];
+                    return Ok(value);
+                }
+                '\\' => match chars.next() {
+                    Some((_, 'n')) => value.push('\n'),
+                    Some((_, 't')) => value.push('\t'),
+                    Some((_, c @ ('"' | '\\'))) => value.push(c),
+                    Some((_, c)) => bail!("unknown escape '\\{c}'"),
+                    None => break,
+                },
+                c => value.push(c),
+            }
+        }
+        bail!("unterminated string")
+    }
+
+    // A single string in parentheses.
+    fn argument(&mut self) -> Result<String> {
+        self.expect('(')?;
+        let value = self.string()?;
+        self.expect(')')?;
+        Ok(value)
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        let rest = self.rest.trim();
+        if !rest.is_empty() {
+            bail!("unexpected \"{rest}\" after the directive");
+        }
+        Ok(())
+    }
+}
@@ -1,13 +1,30 @@
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
+use checking::check_config::check_config;
+use chrono::{Local, NaiveDate};
 use clap::{Arg, Command};
-use compose::run_compose::run_compose;
-use repository::repo::Repository;
-use std::path::PathBuf;
-use submitting::submit::submit_problem;
-use testing::{report::ReportType, test::test_problem};
+use compose::run_compose::{dry_run_compose, run_compose};
+use repository::{context::LaunchOptions, outcome::TestOutcome, repo::Repository};
+use scaffolding::init::init_problem;
+use std::{
+    fs::File,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+use submitting::{
+    diff::diff_problem, restore::restore_problems, status::print_status, submit::submit_problem,
+};
+use testing::{
+    format::ReportFormat,
+    report::{reporter_from_name, ReportConfig},
+    server::{run_report_server, ServerOptions},
+    test::{test_problem, test_problems},
+    watch::watch_problem,
+};
 
+mod checking;
 mod compose;
 mod repository;
+mod scaffolding;
 mod submitting;
 mod testing;
 mod util;
@@ -42,6 +59,101 @@ fn main() -> Result<()> {
                         .required(false)
                         .takes_value(true)
                 )
+                .arg(
+                    Arg::new("all-steps")
+                        .long("all-steps")
+                        .help("Launch all the steps of the problem before submitting, not only linters")
+                        .required(false)
+                        .takes_value(false)
+                )
+                .arg(
+                    Arg::new("force")
+                        .long("force")
+                        .help("Submit even if the checks fail")
+                        .required(false)
+                        .takes_value(false)
+                )
+        )
+        .subcommand(
+            Command::new("status")
+                .about("Show which problems are submitted and whether they changed since")
+                .arg(
+                    Arg::new("path")
+                        .long("path")
+                        .help("Path within the course repository")
+                        .required(false)
+                        .default_value(".")
+                        .hide_default_value(true)
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::new("group")
+                        .long("group")
+                        .help("Show only the problems of the group")
+                        .required(false)
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::new("solutions-repo")
+                        .long("solutions-repo")
+                        .help("Path to the solutions repository")
+                        .required(false)
+                        .takes_value(true)
+                )
+        )
+        .subcommand(
+            Command::new("diff")
+                .about("Show the changes of the solution files since the last submission")
+                .arg(
+                    Arg::new("path")
+                        .long("path")
+                        .help("Path to the problem")
+                        .required(false)
+                        .default_value(".")
+                        .hide_default_value(true)
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::new("solutions-repo")
+                        .long("solutions-repo")
+                        .help("Path to the solutions repository")
+                        .required(false)
+                        .takes_value(true)
+                )
+        )
+        .subcommand(
+            Command::new("restore")
+                .about("Restore the solution files from the solutions repository")
+                .arg(
+                    Arg::new("path")
+                        .long("path")
+                        .help("Path to the problem, or within the course repository with --all")
+                        .required(false)
+                        .default_value(".")
+                        .hide_default_value(true)
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::new("all")
+                        .long("all")
+                        .help("Restore every submitted problem")
+                        .required(false)
+                        .takes_value(false)
+                )
+                .arg(
+                    Arg::new("from-branch")
+                        .long("from-branch")
+                        .help("Branch to restore from instead of the branch of the problem")
+                        .required(false)
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::new("solutions-repo")
+                        .long("solutions-repo")
+                        .help("Path to the solutions repository")
+                        .required(false)
+                        .takes_value(true)
+                )
         )
         .subcommand(
             Command::new("test")
@@ -55,6 +167,22 @@ fn main() -> Result<()> {
                         .hide_default_value(true)
                         .takes_value(true)
                 )
+                .arg(
+                    Arg::new("all")
+                        .long("all")
+                        .help("Test every problem of the course repository and print a summary")
+                        .required(false)
+                        .conflicts_with_all(&["move-files", "group"])
+                        .takes_value(false)
+                )
+                .arg(
+                    Arg::new("group")
+                        .long("group")
+                        .help("Test every problem of the group and print a summary")
+                        .required(false)
+                        .conflicts_with("move-files")
+                        .takes_value(true)
+                )
                 .arg(
                     Arg::new("move-files")
                         .long("move-files")
@@ -74,11 +202,92 @@ fn main() -> Result<()> {
                 .arg(
                     Arg::new("report-to")
                         .long("report-to")
-                        .help("Set system that will accept the results of testing")
+                        .help("Set system that will accept the results of testing: \"no-report\", \"manytask\", \"webhook\" or \"jsonl\"")
                         .required(false)
                         .default_value("no-report")
                         .takes_value(true)
                 )
+                .arg(
+                    Arg::new("report-config")
+                        .long("report-config")
+                        .help("YAML file with the settings of the report systems")
+                        .required(false)
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .help("Format of the testing report: \"human\", \"json\" or \"junit\"")
+                        .required(false)
+                        .default_value("human")
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::new("jobs")
+                        .long("jobs")
+                        .short('j')
+                        .help("Maximum number of commands of a step to run concurrently")
+                        .required(false)
+                        .default_value("1")
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::new("cached")
+                        .long("cached")
+                        .help("Skip commands whose inputs have not changed since their last successful run")
+                        .required(false)
+                        .takes_value(false)
+                )
+                .arg(
+                    Arg::new("verify")
+                        .long("verify")
+                        .help("Path to a checkout of the course with the integrity manifests to check the problem against before testing")
+                        .required(false)
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::new("report-file")
+                        .long("report-file")
+                        .help("File to write the testing report to instead of the standard output")
+                        .required(false)
+                        .takes_value(true)
+                )
+        )
+        .subcommand(
+            Command::new("watch")
+                .about("Test the problem again on every change of its files")
+                .arg(
+                    Arg::new("path")
+                        .long("path")
+                        .help("Path to problem to watch")
+                        .required(false)
+                        .default_value(".")
+                        .hide_default_value(true)
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::new("step")
+                        .long("step")
+                        .help("Run only the step with this name")
+                        .required(false)
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::new("test-filter")
+                        .long("test-filter")
+                        .help("Run only the tests with this string in their name")
+                        .required(false)
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::new("jobs")
+                        .long("jobs")
+                        .short('j')
+                        .help("Maximum number of commands of a step to run concurrently")
+                        .required(false)
+                        .default_value("1")
+                        .takes_value(true)
+                )
         )
         .subcommand(
             Command::new("compose")
@@ -95,9 +304,119 @@ fn main() -> Result<()> {
                     Arg::new("output")
                         .long("output")
                         .help("Directory where output will be stored")
+                        .required_unless_present("dry-run")
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::new("dry-run")
+                        .long("dry-run")
+                        .help("Print the diff of every changed source instead of writing the output")
+                        .required(false)
+                        .takes_value(false)
+                        .conflicts_with_all(&["output", "check"])
+                )
+                .arg(
+                    Arg::new("check")
+                        .long("check")
+                        .help("Fail if the composed output doesn't compile")
+                        .required(false)
+                        .takes_value(false)
+                )
+                .arg(
+                    Arg::new("date")
+                        .long("date")
+                        .help("Date in YYYY-MM-DD format to decide which solutions are public, today by default")
+                        .required(false)
+                        .takes_value(true)
+                )
+        )
+        .subcommand(
+            Command::new("check-config")
+                .about("Check the config of the problem")
+                .arg(
+                    Arg::new("path")
+                        .long("path")
+                        .help("Path to the problem")
+                        .required(false)
+                        .default_value(".")
+                        .hide_default_value(true)
+                )
+                .arg(
+                    Arg::new("all")
+                        .long("all")
+                        .help("Check the configs of all problems of the course repository")
+                        .required(false)
+                        .takes_value(false)
+                )
+        )
+        .subcommand(
+            Command::new("init")
+                .about("Create a new problem from a template")
+                .arg(
+                    Arg::new("path")
+                        .long("path")
+                        .help("Path to the course repository")
+                        .required(false)
+                        .default_value(".")
+                        .hide_default_value(true)
+                )
+                .arg(
+                    Arg::new("group")
+                        .long("group")
+                        .help("Group of the problem")
                         .required(true)
                         .takes_value(true)
                 )
+                .arg(
+                    Arg::new("title")
+                        .long("title")
+                        .help("Title of the problem")
+                        .required(true)
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::new("template")
+                        .long("template")
+                        .help("Template of the problem: \"lib\", \"bin\", \"proc-macro\" or \"async\"")
+                        .required(false)
+                        .default_value("lib")
+                        .takes_value(true)
+                )
+        )
+        .subcommand(
+            Command::new("report-server")
+                .about("Run a local server that prints the reports sent to it")
+                .arg(
+                    Arg::new("address")
+                        .long("address")
+                        .help("Address to listen on")
+                        .required(false)
+                        .default_value("127.0.0.1:8080")
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::new("status")
+                        .long("status")
+                        .help("Status code to answer with")
+                        .required(false)
+                        .default_value("200")
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::new("fail-first")
+                        .long("fail-first")
+                        .help("Number of the first requests to answer with 500 code")
+                        .required(false)
+                        .default_value("0")
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::new("max-requests")
+                        .long("max-requests")
+                        .help("Stop after answering this number of requests")
+                        .required(false)
+                        .takes_value(true)
+                )
         )
         .arg_required_else_help(true)
         .get_matches();
@@ -107,27 +426,149 @@ fn main() -> Result<()> {
             let path: PathBuf = submit_matches.value_of("path").unwrap().into();
             let message = submit_matches.value_of("message").unwrap();
             let solutions_repo = submit_matches.value_of("solutions-repo").map(PathBuf::from);
-            submit_problem(&path, message, solutions_repo)
+            submit_problem(
+                &path,
+                message,
+                solutions_repo,
+                submit_matches.is_present("all-steps"),
+                submit_matches.is_present("force"),
+            )
+        }
+        Some(("status", status_matches)) => {
+            let path: PathBuf = status_matches.value_of("path").unwrap().into();
+            let solutions_repo = status_matches.value_of("solutions-repo").map(PathBuf::from);
+            print_status(&path, status_matches.value_of("group"), solutions_repo)
+        }
+        Some(("diff", diff_matches)) => {
+            let path: PathBuf = diff_matches.value_of("path").unwrap().into();
+            let solutions_repo = diff_matches.value_of("solutions-repo").map(PathBuf::from);
+            diff_problem(&path, solutions_repo)
+        }
+        Some(("restore", restore_matches)) => {
+            let path: PathBuf = restore_matches.value_of("path").unwrap().into();
+            let solutions_repo = restore_matches
+                .value_of("solutions-repo")
+                .map(PathBuf::from);
+            restore_problems(
+                &path,
+                restore_matches.is_present("all"),
+                restore_matches.value_of("from-branch"),
+                solutions_repo,
+            )
         }
         Some(("test", test_matches)) => {
             let path: PathBuf = test_matches.value_of("path").unwrap().into();
             let repository = Repository::from_path(&path)?;
-            let problem = repository.problem_from_path(&path)?;
-            let report = ReportType::from_name(test_matches.value_of("report-to").unwrap())?;
-            if let Some(solutions_repo) = test_matches.value_of("move-files") {
-                let checkout_branch = test_matches.value_of("checkout-branch").is_some();
-                let solutions_repo: PathBuf = solutions_repo.into();
-                problem.move_solution_files_from(&solutions_repo, checkout_branch)?;
+            let report_config =
+                ReportConfig::from_file(test_matches.value_of("report-config").map(Path::new))?;
+            let report =
+                reporter_from_name(test_matches.value_of("report-to").unwrap(), report_config)?;
+            let format = ReportFormat::from_name(test_matches.value_of("format").unwrap())?;
+            let jobs = test_matches
+                .value_of("jobs")
+                .unwrap()
+                .parse()
+                .context("number of jobs is not a number")?;
+            let cached = test_matches.is_present("cached");
+            let options = LaunchOptions::new(format.echo_output(), jobs, cached);
+            let group = test_matches.value_of("group");
+            let batch = test_matches.is_present("all") || group.is_some();
+            let verify = test_matches.value_of("verify").map(PathBuf::from);
+            if let Some(course) = &verify {
+                let course = course
+                    .canonicalize()
+                    .with_context(|| format!("failed to find course checkout {course:?}"))?;
+                if repository.get_path().canonicalize().ok() == Some(course) {
+                    bail!("--verify needs a checkout of the course separate from the tested one");
+                }
+            }
+            let outcomes = if batch {
+                let problems = repository.problems(group)?;
+                test_problems(problems, &options, verify.as_deref())
+            } else {
+                let problem = repository.problem_from_path(&path)?;
+                if let Some(solutions_repo) = test_matches.value_of("move-files") {
+                    let checkout_branch = test_matches.is_present("checkout-branch");
+                    let solutions_repo: PathBuf = solutions_repo.into();
+                    problem.move_solution_files_from(
+                        &solutions_repo,
+                        checkout_branch,
+                        repository.get_settings(),
+                    )?;
+                }
+                vec![test_problem(problem, &options, verify.as_deref())]
+            };
+            let mut writer: Box<dyn Write> = match test_matches.value_of("report-file") {
+                Some(path) => Box::new(File::create(path).context("failed to create report file")?),
+                None => Box::new(io::stdout()),
+            };
+            if batch {
+                format.write_batch(&outcomes, &mut writer)?;
+            } else {
+                format.write(&outcomes[0], &mut writer)?;
             }
-            // TODO: Make testing errors more clear
-            let testing_result = test_problem(problem);
-            let report_push = report.push_report(testing_result.is_err());
-            testing_result.and(report_push)
+            report.push_report(&outcomes)?;
+            if outcomes.iter().any(TestOutcome::failed) {
+                bail!("testing failed")
+            }
+            Ok(())
+        }
+        Some(("watch", watch_matches)) => {
+            let path: PathBuf = watch_matches.value_of("path").unwrap().into();
+            let jobs = watch_matches
+                .value_of("jobs")
+                .unwrap()
+                .parse()
+                .context("number of jobs is not a number")?;
+            let options = LaunchOptions::new(true, jobs, false)
+                .with_test_filter(watch_matches.value_of("test-filter"));
+            watch_problem(&path, watch_matches.value_of("step"), &options)
         }
         Some(("compose", compose_matches)) => {
             let input: PathBuf = compose_matches.value_of("input").unwrap().into();
+            let today = match compose_matches.value_of("date") {
+                Some(date) => NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                    .with_context(|| format!("invalid date \"{date}\", expected YYYY-MM-DD"))?,
+                None => Local::now().date_naive(),
+            };
+            if compose_matches.is_present("dry-run") {
+                return dry_run_compose(&input, today);
+            }
             let output: PathBuf = compose_matches.value_of("output").unwrap().into();
-            run_compose(&input, &output)
+            run_compose(&input, &output, today, compose_matches.is_present("check"))
+        }
+        Some(("check-config", check_matches)) => {
+            let path: PathBuf = check_matches.value_of("path").unwrap().into();
+            check_config(&path, check_matches.is_present("all"))
+        }
+        Some(("init", init_matches)) => {
+            let path: PathBuf = init_matches.value_of("path").unwrap().into();
+            init_problem(
+                &path,
+                init_matches.value_of("group").unwrap(),
+                init_matches.value_of("title").unwrap(),
+                init_matches.value_of("template").unwrap(),
+            )
+        }
+        Some(("report-server", server_matches)) => {
+            let options = ServerOptions {
+                status: server_matches
+                    .value_of("status")
+                    .unwrap()
+                    .parse()
+                    .context("status is not a number")?,
+                fail_first: server_matches
+                    .value_of("fail-first")
+                    .unwrap()
+                    .parse()
+                    .context("number of failing requests is not a number")?,
+                max_requests: server_matches
+                    .value_of("max-requests")
+                    .map(str::parse)
+                    .transpose()
+                    .context("number of requests is not a number")?,
+            };
+            run_report_server(server_matches.value_of("address").unwrap(), &options)
         }
         _ => unreachable!(),
     }
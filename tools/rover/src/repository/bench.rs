@@ -0,0 +1,233 @@
+use super::compile_fail::{should_bless, BLESS_VARIABLE};
+use anyhow::{bail, Context, Error, Result};
+use serde::Deserialize;
+use serde_yaml::Mapping;
+use std::{collections::BTreeMap, fs, path::Path};
+
+const BASELINE_PATH: &str = "benches/baseline.json";
+const MAX_TIME_KEY: &str = "max-time";
+const MAX_RATIO_KEY: &str = "max-ratio";
+const REFERENCE_KEY: &str = "reference";
+// Nanoseconds in a unit, in the spellings of criterion and libtest.
+const UNITS: [(&str, f64); 6] = [
+    ("ps", 1e-3),
+    ("ns", 1.0),
+    ("µs", 1e3),
+    ("us", 1e3),
+    ("ms", 1e6),
+    ("s", 1e9),
+];
+
+// Limits of a benchmark: the time of an iteration, and the ratio to the time of the reference
+// benchmark of the same run or, without it, to the baseline stored with the problem.
+#[derive(Debug)]
+pub struct Threshold {
+    name: String,
+    max_time: Option<f64>,
+    max_ratio: Option<f64>,
+    reference: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RawThreshold {
+    max_time: Option<String>,
+    max_ratio: Option<f64>,
+    reference: Option<String>,
+    #[serde(flatten)]
+    pub unknown: Mapping,
+}
+
+impl RawThreshold {
+    // Invalid limits are skipped and returned with their keys, like in the limits of commands.
+    pub fn parse(&self, name: &str) -> (Threshold, Vec<(&'static str, Error)>) {
+        let mut errors = vec![];
+        let max_time = self
+            .max_time
+            .as_deref()
+            .and_then(|text| match parse_time(text) {
+                Ok(time) => Some(time),
+                Err(err) => {
+                    errors.push((MAX_TIME_KEY, err));
+                    None
+                }
+            });
+        let max_ratio = match self.max_ratio {
+            Some(ratio) if ratio.is_nan() || ratio <= 0.0 => {
+                errors.push((MAX_RATIO_KEY, Error::msg("ratio must be positive")));
+                None
+            }
+            ratio => ratio,
+        };
+        if self.reference.is_some() && self.max_ratio.is_none() {
+            errors.push((REFERENCE_KEY, Error::msg("reference requires max-ratio")));
+        }
+        let threshold = Threshold {
+            name: name.to_string(),
+            max_time,
+            max_ratio,
+            reference: self.reference.clone(),
+        };
+        (threshold, errors)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.max_time.is_none() && self.max_ratio.is_none()
+    }
+}
+
+fn parse_time(text: &str) -> Result<f64> {
+    let trimmed = text.trim();
+    let parsed = UNITS.iter().find_map(|(unit, nanos)| {
+        let number = trimmed.strip_suffix(unit)?.trim().replace(',', "");
+        Some(number.parse::<f64>().ok()? * nanos)
+    });
+    match parsed {
+        Some(nanos) if nanos.is_finite() && nanos >= 0.0 => Ok(nanos),
+        _ => bail!("\"{text}\" is not a time like \"2ms\", \"150us\" or \"1.5s\""),
+    }
+}
+
+fn format_time(nanos: f64) -> String {
+    let (unit, scale) = UNITS
+        .iter()
+        .filter(|(unit, _)| *unit != "us")
+        .rev()
+        .find(|(_, scale)| nanos >= *scale)
+        .unwrap_or(&UNITS[0]);
+    format!("{:.2} {unit}", nanos / scale)
+}
+
+// Times of an iteration in nanoseconds by the benchmark name. Criterion prints
+// `group/name  time: [low estimate high]`, with the name on the line above if it's long, and
+// libtest prints `test name ... bench: 1,234 ns/iter (+/- 56)`.
+pub fn parse_results(stdout: &str) -> BTreeMap<String, f64> {
+    let mut results = BTreeMap::new();
+    let mut previous = "";
+    for line in stdout.lines() {
+        if let Some((name, time)) = line.split_once("time:") {
+            let name = match name.trim() {
+                "" => previous,
+                name => name,
+            };
+            let estimate = time
+                .trim()
+                .strip_prefix('[')
+                .and_then(|time| time.strip_suffix(']'))
+                .map(|time| time.split_whitespace().collect::<Vec<_>>());
+            if let Some([_, _, value, unit, _, _]) = estimate.as_deref() {
+                if let Ok(nanos) = parse_time(&format!("{value}{unit}")) {
+                    results.insert(name.to_string(), nanos);
+                }
+            }
+        } else if let Some((name, time)) = line
+            .strip_prefix("test ")
+            .and_then(|line| line.split_once(" ... bench:"))
+        {
+            if let Some(time) = time.split_whitespace().next() {
+                if let Ok(nanos) = parse_time(&format!("{time}ns")) {
+                    results.insert(name.trim().to_string(), nanos);
+                }
+            }
+        }
+        if !line.trim().is_empty() {
+            previous = line.trim();
+        }
+    }
+    results
+}
+
+fn load_baseline(workdir: &Path) -> Result<BTreeMap<String, f64>> {
+    let path = workdir.join(BASELINE_PATH);
+    match fs::read(&path) {
+        Ok(content) => serde_json::from_slice(&content)
+            .with_context(|| format!("invalid benchmark baseline {path:?}")),
+        Err(_) => Ok(BTreeMap::new()),
+    }
+}
+
+fn save_baseline(workdir: &Path, results: &BTreeMap<String, f64>) -> Result<()> {
+    let path = workdir.join(BASELINE_PATH);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("failed to create {dir:?}"))?;
+    }
+    // Picoseconds are the finest unit printed, the rest is noise of the unit conversion.
+    let rounded = results
+        .iter()
+        .map(|(name, nanos)| (name, (nanos * 1e3).round() / 1e3))
+        .collect::<BTreeMap<_, _>>();
+    let mut json = serde_json::to_vec_pretty(&rounded).context("failed to serialize baseline")?;
+    json.push(b'\n');
+    fs::write(&path, json).with_context(|| format!("failed to write {path:?}"))
+}
+
+impl Threshold {
+    // Describes why the benchmark is too slow or can't be compared.
+    fn check(
+        &self,
+        results: &BTreeMap<String, f64>,
+        baseline: &BTreeMap<String, f64>,
+    ) -> Option<String> {
+        let Some(&time) = results.get(&self.name) else {
+            return Some("did not run".to_string());
+        };
+        if let Some(max_time) = self.max_time {
+            if time > max_time {
+                return Some(format!(
+                    "{} exceeds the maximum of {}",
+                    format_time(time),
+                    format_time(max_time)
+                ));
+            }
+        }
+        let max_ratio = self.max_ratio?;
+        let (reference, reference_name) = match &self.reference {
+            Some(name) => match results.get(name) {
+                Some(&reference) => (reference, name.as_str()),
+                None => return Some(format!("reference {name} did not run")),
+            },
+            None => match baseline.get(&self.name) {
+                Some(&reference) => (reference, "the baseline"),
+                None => {
+                    return Some(format!(
+                        "no baseline in {BASELINE_PATH}, run with {BLESS_VARIABLE}=1 to write it"
+                    ))
+                }
+            },
+        };
+        let ratio = time / reference;
+        (ratio > max_ratio).then(|| {
+            format!(
+                "{} is {ratio:.2}x of {reference_name} {}, the maximum is {max_ratio}x",
+                format_time(time),
+                format_time(reference)
+            )
+        })
+    }
+}
+
+// Checks the results against the thresholds. The baseline is written from the results first when
+// blessing.
+pub fn check_results(workdir: &Path, thresholds: &[Threshold], stdout: &str) -> Result<()> {
+    let results = parse_results(stdout);
+    if should_bless() {
+        save_baseline(workdir, &results)?;
+    }
+    let baseline = load_baseline(workdir)?;
+    let failures = thresholds
+        .iter()
+        .filter_map(|threshold| {
+            let failure = threshold.check(&results, &baseline)?;
+            Some(format!("{}: {failure}", threshold.name))
+        })
+        .collect::<Vec<_>>();
+    if failures.is_empty() {
+        return Ok(());
+    }
+    bail!(
+        "{} of {} benchmark(s) failed:\n{}",
+        failures.len(),
+        thresholds.len(),
+        failures.join("\n")
+    )
+}
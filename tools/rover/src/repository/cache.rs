@@ -0,0 +1,149 @@
+use super::{command::Command, config::Config};
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+const CACHE_FILE: &str = "target/rover-cache.json";
+const CARGO_LOCK: &str = "Cargo.lock";
+pub const TARGET_FOLDER: &str = "target";
+
+// Results are keyed by the step and the command, and are fresh while the fingerprint of the
+// problem files, Cargo.lock, the toolchain and the command is the same.
+pub struct Cache {
+    path: PathBuf,
+    workdir: PathBuf,
+    toolchain: String,
+    sources: Vec<u8>,
+    cargo_lock: Vec<u8>,
+    stored: Vec<(String, String)>,
+    entries: BTreeMap<String, String>,
+}
+
+impl Cache {
+    pub fn load(config: &Config) -> Result<Self> {
+        let workdir = config.get_workdir();
+        let path = workdir.join(CACHE_FILE);
+        let entries = match fs::read(&path) {
+            Ok(content) => serde_json::from_slice(&content).unwrap_or_default(),
+            Err(_) => BTreeMap::new(),
+        };
+        Ok(Self {
+            path,
+            workdir: workdir.to_path_buf(),
+            toolchain: config.get_toolchain().name().to_string(),
+            sources: hash_sources(workdir)?,
+            cargo_lock: hash_cargo_lock(workdir)?,
+            stored: vec![],
+            entries,
+        })
+    }
+
+    fn key(step: &str, command: &Command) -> String {
+        format!("{step}/{}", command.name())
+    }
+
+    fn fingerprint(&self, cargo_lock: &[u8], command: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(&self.sources);
+        hasher.update(cargo_lock);
+        hasher.update(&self.toolchain);
+        hasher.update(command);
+        hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+
+    pub fn is_fresh(&self, step: &str, command: &Command) -> bool {
+        self.entries.get(&Self::key(step, command))
+            == Some(&self.fingerprint(&self.cargo_lock, &format!("{command:?}")))
+    }
+
+    pub fn store(&mut self, step: &str, command: &Command) {
+        self.stored
+            .push((Self::key(step, command), format!("{command:?}")));
+    }
+
+    pub fn invalidate(&mut self, step: &str, command: &Command) {
+        let key = Self::key(step, command);
+        self.stored.retain(|(stored, _)| *stored != key);
+        self.entries.remove(&key);
+    }
+
+    // The results are stored only if the files they were taken on didn't change during the run,
+    // like when a file is saved while the steps run. Cargo.lock is taken as the run left it,
+    // since cargo may update it.
+    pub fn save(mut self) -> Result<()> {
+        if !self.stored.is_empty() && hash_sources(&self.workdir)? == self.sources {
+            let cargo_lock = hash_cargo_lock(&self.workdir)?;
+            for (key, command) in std::mem::take(&mut self.stored) {
+                let fingerprint = self.fingerprint(&cargo_lock, &command);
+                self.entries.insert(key, fingerprint);
+            }
+        }
+        let dir = self.path.parent().context("cache file has no parent")?;
+        fs::create_dir_all(dir).context("failed to create cache directory")?;
+        let content =
+            serde_json::to_vec_pretty(&self.entries).context("failed to serialize cache")?;
+        fs::write(&self.path, content).with_context(|| format!("failed to write {:?}", self.path))
+    }
+}
+
+// Every file of the problem except the build directory, in a stable order.
+pub fn problem_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    let mut entries = fs::read_dir(dir)
+        .with_context(|| format!("failed to read {dir:?}"))?
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("failed to read {dir:?}"))?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let path = entry.path();
+        if path.is_dir() {
+            if entry.file_name() != TARGET_FOLDER {
+                problem_files(&path, files)?;
+            }
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+// Every file of the problem except Cargo.lock.
+fn hash_sources(workdir: &Path) -> Result<Vec<u8>> {
+    let mut hasher = Sha256::new();
+    let mut files = vec![];
+    problem_files(workdir, &mut files)?;
+    for file in files {
+        if file != workdir.join(CARGO_LOCK) {
+            hash_file(&mut hasher, &file)?;
+        }
+    }
+    Ok(hasher.finalize().to_vec())
+}
+
+// The lock file of the problem or of its workspace.
+fn hash_cargo_lock(workdir: &Path) -> Result<Vec<u8>> {
+    let mut hasher = Sha256::new();
+    if let Some(cargo_lock) = workdir
+        .ancestors()
+        .map(|dir| dir.join(CARGO_LOCK))
+        .find(|path| path.is_file())
+    {
+        hash_file(&mut hasher, &cargo_lock)?;
+    }
+    Ok(hasher.finalize().to_vec())
+}
+
+fn hash_file(hasher: &mut Sha256, path: &Path) -> Result<()> {
+    let content = fs::read(path).with_context(|| format!("failed to read {path:?}"))?;
+    hasher.update(path.to_string_lossy().as_bytes());
+    hasher.update((content.len() as u64).to_le_bytes());
+    hasher.update(&content);
+    Ok(())
+}
@@ -1,4 +1,7 @@
+use super::bench::Threshold;
 use anyhow::{bail, Result};
+use serde::Deserialize;
+use std::{collections::BTreeMap, path::PathBuf};
 
 #[derive(Debug)]
 pub enum Command {
@@ -10,10 +13,76 @@ pub enum Command {
     CargoTest,
     CargoTestDebug,
     CargoMiriTest,
-    CargoCompileTestMiniFrunk,
-    CargoCompileTestOrm,
-    CargoCompileTestSnapshot,
     PythonTest,
+    // Cargo features that each must fail to compile with the errors of their snapshot.
+    CompileFail(Vec<String>),
+    CargoBench(Vec<Threshold>),
+    Custom(CustomCommand),
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Expectation {
+    #[default]
+    Success,
+    Failure,
+}
+
+#[derive(Debug)]
+pub struct CustomCommand {
+    name: String,
+    program: String,
+    args: Vec<String>,
+    env: BTreeMap<String, String>,
+    workdir: Option<PathBuf>,
+    expect: Expectation,
+    features: Vec<String>,
+}
+
+impl CustomCommand {
+    pub fn new(
+        name: String,
+        program: String,
+        args: Vec<String>,
+        env: BTreeMap<String, String>,
+        workdir: Option<PathBuf>,
+        expect: Expectation,
+        features: Vec<String>,
+    ) -> Self {
+        Self {
+            name,
+            program,
+            args,
+            env,
+            workdir,
+            expect,
+            features,
+        }
+    }
+
+    pub fn program(&self) -> &str {
+        &self.program
+    }
+
+    pub fn args(&self) -> &[String] {
+        self.args.as_slice()
+    }
+
+    pub fn env(&self) -> &BTreeMap<String, String> {
+        &self.env
+    }
+
+    pub fn workdir(&self) -> Option<&PathBuf> {
+        self.workdir.as_ref()
+    }
+
+    pub fn expect(&self) -> Expectation {
+        self.expect
+    }
+
+    pub fn features(&self) -> &[String] {
+        self.features.as_slice()
+    }
 }
 
 impl Command {
@@ -27,14 +96,30 @@ impl Command {
             "cargo-test" => Self::CargoTest,
             "cargo-test-debug" => Self::CargoTestDebug,
             "cargo-miri-test" => Self::CargoMiriTest,
-            "cargo-compile-test-mini-frunk" => Self::CargoCompileTestMiniFrunk,
-            "cargo-compile-test-orm" => Self::CargoCompileTestOrm,
-            "cargo-compile-test-snapshot" => Self::CargoCompileTestSnapshot,
             "python-test" => Self::PythonTest,
+            "compile-fail" => Self::CompileFail(vec![]),
+            "cargo-bench" => Self::CargoBench(vec![]),
             name => bail!("command \"{name}\" is not supported"),
         })
     }
 
+    pub fn name(&self) -> &str {
+        match self {
+            Self::ForbidUnsafe => "forbid-unsafe",
+            Self::ForbidCollections => "forbid-collections",
+            Self::ForbidStd => "forbid-std",
+            Self::CargoFmt => "cargo-fmt",
+            Self::CargoClippy => "cargo-clippy",
+            Self::CargoTest => "cargo-test",
+            Self::CargoTestDebug => "cargo-test-debug",
+            Self::CargoMiriTest => "cargo-miri-test",
+            Self::PythonTest => "python-test",
+            Self::CompileFail(_) => "compile-fail",
+            Self::CargoBench(_) => "cargo-bench",
+            Self::Custom(custom) => &custom.name,
+        }
+    }
+
     pub fn get_shell_line(&self) -> Result<String> {
         Ok(match self {
             Self::ForbidUnsafe => bail!("no shell line for ForbidUnsafe"),
@@ -42,13 +127,15 @@ impl Command {
             Self::ForbidStd => bail!("no shell line for ForbidStd"),
             Self::CargoFmt => "cargo fmt --check".to_string(),
             Self::CargoClippy => "cargo clippy --release -- -D warnings".to_string(),
-            Self::CargoTest => "cargo test --release".to_string(),
-            Self::CargoTestDebug => "cargo test".to_string(),
-            Self::CargoMiriTest => "cargo miri test --release".to_string(),
-            Self::CargoCompileTestMiniFrunk => bail!("no shell line for CargoCompileTestMiniFrunk"),
-            Self::CargoCompileTestOrm => bail!("no shell line for CargoCompileTestOrm"),
-            Self::CargoCompileTestSnapshot => bail!("no shell line for CargoCompileTestSnapshot"),
+            Self::CargoTest => "cargo test --release --no-fail-fast".to_string(),
+            Self::CargoTestDebug => "cargo test --no-fail-fast".to_string(),
+            Self::CargoMiriTest => "cargo miri test --release --no-fail-fast".to_string(),
             Self::PythonTest => "python3 test.py".to_string(),
+            Self::CompileFail(_) => {
+                "cargo test --no-run --message-format json --features".to_string()
+            }
+            Self::CargoBench(_) => "cargo bench".to_string(),
+            Self::Custom(custom) => bail!("no shell line for custom command {}", custom.name),
         })
     }
 }
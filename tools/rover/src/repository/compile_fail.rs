@@ -0,0 +1,114 @@
+use anyhow::{Context, Result};
+use similar::TextDiff;
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+const SNAPSHOTS_FOLDER: &str = "tests/compile-fail";
+const SNAPSHOT_EXTENSION: &str = "stderr";
+pub const BLESS_VARIABLE: &str = "ROVER_BLESS";
+
+fn snapshot_path(workdir: &Path, case: &str) -> PathBuf {
+    workdir
+        .join(SNAPSHOTS_FOLDER)
+        .join(format!("{case}.{SNAPSHOT_EXTENSION}"))
+}
+
+// Paths of the diagnostics are relative to the workspace root, which may be above the problem.
+fn relative_file(workdir: &Path, file: &str) -> String {
+    let file = Path::new(file);
+    let absolute = if file.is_absolute() {
+        Some(file.to_path_buf())
+    } else {
+        workdir
+            .ancestors()
+            .map(|dir| dir.join(file))
+            .find(|path| path.is_file())
+    };
+    absolute
+        .as_deref()
+        .and_then(|path| path.strip_prefix(workdir).ok())
+        .unwrap_or(file)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+// The errors of `cargo --message-format json` as `error[CODE]: message` with the primary
+// location, which is stable across compiler versions unlike the rendered output.
+pub fn normalize_errors(stdout: &[u8], workdir: &Path) -> (String, Vec<String>) {
+    let mut errors: Vec<String> = vec![];
+    let mut rendered = vec![];
+    for line in stdout.split(|byte| *byte == b'\n') {
+        let Ok(event) = serde_json::from_slice::<serde_json::Value>(line) else {
+            continue;
+        };
+        let message = &event["message"];
+        if event["reason"] != "compiler-message" || message["level"] != "error" {
+            continue;
+        }
+        let Some(text) = message["message"].as_str() else {
+            continue;
+        };
+        if text.starts_with("aborting due to") {
+            continue;
+        }
+        let mut error = match message["code"]["code"].as_str() {
+            Some(code) => format!("error[{code}]: {text}\n"),
+            None => format!("error: {text}\n"),
+        };
+        let spans = message["spans"].as_array().into_iter().flatten();
+        for span in spans.filter(|span| span["is_primary"] == true) {
+            if let (Some(file), Some(line), Some(column)) = (
+                span["file_name"].as_str(),
+                span["line_start"].as_u64(),
+                span["column_start"].as_u64(),
+            ) {
+                let file = relative_file(workdir, file);
+                error += &format!(" --> {file}:{line}:{column}\n");
+            }
+        }
+        // Errors of a library are repeated for each target that builds it.
+        if !errors.contains(&error) {
+            errors.push(error);
+            rendered.extend(message["rendered"].as_str().map(str::to_string));
+        }
+    }
+    (errors.concat(), rendered)
+}
+
+// Snapshots and baselines are written from the results instead of being compared.
+pub fn should_bless() -> bool {
+    env::var_os(BLESS_VARIABLE).is_some_and(|value| !value.is_empty() && value != "0")
+}
+
+// Compares the errors with the snapshot, or writes the snapshot when blessing. Returns the
+// description of a mismatch.
+pub fn check_snapshot(workdir: &Path, case: &str, actual: &str) -> Result<Option<String>> {
+    let path = snapshot_path(workdir, case);
+    let relative = path.strip_prefix(workdir).unwrap_or(&path).display();
+    if should_bless() {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).with_context(|| format!("failed to create {dir:?}"))?;
+        }
+        fs::write(&path, actual).with_context(|| format!("failed to write {path:?}"))?;
+        return Ok(None);
+    }
+    let Ok(expected) = fs::read_to_string(&path) else {
+        return Ok(Some(format!(
+            "no snapshot {relative}, the errors are:\n{actual}\
+             run with {BLESS_VARIABLE}=1 to write it"
+        )));
+    };
+    if expected.trim_end() == actual.trim_end() {
+        return Ok(None);
+    }
+    let diff = TextDiff::from_lines(expected.as_str(), actual)
+        .unified_diff()
+        .header(&relative.to_string(), "actual")
+        .to_string();
+    Ok(Some(format!(
+        "errors differ from the snapshot:\n{}",
+        diff.trim_end()
+    )))
+}
@@ -1,11 +1,25 @@
-#![allow(clippy::needless_question_mark)]
-
-use super::{command::Command, context::CommandContext, step::Step, toolchain::Toolchain};
-use anyhow::{Context, Result};
+use super::{
+    bench::{RawThreshold, Threshold},
+    command::{Command, CustomCommand, Expectation},
+    context::CommandContext,
+    diagnostic::Diagnostic,
+    limits::{Limits, RawLimits},
+    positions::{Position, Positions},
+    sandbox::Sandbox,
+    scoring::Scoring,
+    step::{Step, StepCommand},
+    toolchain::Toolchain,
+};
+use anyhow::{bail, Context, Result};
 use glob::{glob_with, MatchOptions};
-use serde_yaml::{from_reader, Value};
+use serde::{
+    de::{self, value::MapAccessDeserializer, MapAccess, SeqAccess, Visitor},
+    Deserialize, Deserializer,
+};
+use serde_yaml::Mapping;
 use std::{
-    fs::File,
+    collections::{BTreeMap, HashMap},
+    fmt, fs,
     path::{Path, PathBuf},
 };
 
@@ -16,79 +30,299 @@ pub struct Config {
     relative_user_files: Vec<PathBuf>,
     absolute_user_files: Vec<PathBuf>,
     steps: Vec<Step>,
+    scoring: Scoring,
+    sandbox: Option<Sandbox>,
 }
 
-impl Config {
-    pub fn from_yml(path: &Path) -> Result<Self> {
-        let file = File::open(path).context("no yml file with config")?;
-        let yml: Value = from_reader(file).context("unable to read yml file with config")?;
-        let yml = yml.as_mapping().context("the yml is not a mapping")?;
-        let workdir = path.parent().context("yml has no parent")?.to_path_buf();
-        let toolchain = Toolchain::from_name(
-            yml[&Value::String("toolchain".to_string())]
-                .as_str()
-                .context("toolchain is not a string")?,
-        )?;
-        let patterns = yml[&Value::String("allowed-patterns".to_string())]
-            .as_sequence()
-            .context("user files are not in a list")?
-            .iter()
-            .map(|value| Ok(value.as_str().context("user file path is not a string")?))
-            .collect::<Result<Vec<_>>>()?;
-        let (relative_user_files, absolute_user_files) =
-            Self::get_matching_user_files(&workdir, patterns.as_slice())?;
-        let steps = yml[&Value::String("steps".to_string())]
-            .as_mapping()
-            .context("steps is not a mapping")?
-            .iter()
-            .map(|(key, value)| {
-                let name = key
-                    .as_str()
-                    .context("name of the step is not a string")?
-                    .to_string();
-                let commands = value
-                    .as_sequence()
-                    .context("the step commands are not a sequence")?
-                    .iter()
-                    .map(|value| Command::from_name(value.as_str().unwrap()).unwrap())
-                    .collect();
-                Ok(Step::new(name, commands))
-            })
-            .collect::<Result<Vec<_>>>()?;
-        Ok(Self {
-            workdir,
-            toolchain,
-            relative_user_files,
-            absolute_user_files,
-            steps,
-        })
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct RawConfig {
+    toolchain: RawToolchain,
+    allowed_patterns: Vec<String>,
+    steps: RawSteps,
+    #[serde(default)]
+    scoring: Scoring,
+    sandbox: Option<Sandbox>,
+    #[serde(flatten)]
+    unknown: Mapping,
+}
+
+enum RawToolchain {
+    Name(String),
+    Detailed(Box<DetailedToolchain>),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct DetailedToolchain {
+    channel: String,
+    #[serde(default)]
+    components: Vec<String>,
+    #[serde(default)]
+    targets: Vec<String>,
+    #[serde(flatten)]
+    unknown: Mapping,
+}
+
+// Steps keep the order of the config.
+struct RawSteps(Vec<(String, RawStep)>);
+
+enum RawStep {
+    Commands(Vec<RawCommand>),
+    Detailed(Box<DetailedStep>),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct DetailedStep {
+    commands: Vec<RawCommand>,
+    #[serde(flatten)]
+    limits: RawLimits,
+    #[serde(flatten)]
+    unknown: Mapping,
+}
+
+enum RawCommand {
+    Name(String),
+    Detailed(Box<DetailedCommand>),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct DetailedCommand {
+    name: Option<String>,
+    program: Option<String>,
+    args: Option<Vec<String>>,
+    env: Option<BTreeMap<String, String>>,
+    workdir: Option<PathBuf>,
+    expect: Option<Expectation>,
+    features: Option<Vec<String>>,
+    benchmarks: Option<BTreeMap<String, RawThreshold>>,
+    #[serde(flatten)]
+    limits: RawLimits,
+    #[serde(flatten)]
+    unknown: Mapping,
+}
+
+impl<'de> Deserialize<'de> for RawToolchain {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ToolchainVisitor;
+
+        impl<'de> Visitor<'de> for ToolchainVisitor {
+            type Value = RawToolchain;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a toolchain name or a mapping with channel, components and targets")
+            }
+
+            fn visit_str<E: de::Error>(self, name: &str) -> Result<Self::Value, E> {
+                Ok(RawToolchain::Name(name.to_string()))
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+                DetailedToolchain::deserialize(MapAccessDeserializer::new(map))
+                    .map(|toolchain| RawToolchain::Detailed(Box::new(toolchain)))
+            }
+        }
+
+        deserializer.deserialize_any(ToolchainVisitor)
     }
+}
 
-    pub fn get_steps(&self) -> &[Step] {
-        self.steps.as_slice()
+impl<'de> Deserialize<'de> for RawSteps {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct StepsVisitor;
+
+        impl<'de> Visitor<'de> for StepsVisitor {
+            type Value = RawSteps;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a mapping of step names to steps")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut steps = vec![];
+                while let Some((name, step)) = map.next_entry()? {
+                    steps.push((name, step));
+                }
+                Ok(RawSteps(steps))
+            }
+        }
+
+        deserializer.deserialize_map(StepsVisitor)
     }
+}
 
-    pub fn get_toolchain(&self) -> &Toolchain {
-        &self.toolchain
+impl<'de> Deserialize<'de> for RawStep {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct StepVisitor;
+
+        impl<'de> Visitor<'de> for StepVisitor {
+            type Value = RawStep;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a list of commands or a mapping with commands and limits")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut commands = vec![];
+                while let Some(command) = seq.next_element()? {
+                    commands.push(command);
+                }
+                Ok(RawStep::Commands(commands))
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+                DetailedStep::deserialize(MapAccessDeserializer::new(map))
+                    .map(|step| RawStep::Detailed(Box::new(step)))
+            }
+        }
+
+        deserializer.deserialize_any(StepVisitor)
     }
+}
 
-    pub fn get_relative_user_files(&self) -> &[PathBuf] {
-        self.relative_user_files.as_slice()
+impl<'de> Deserialize<'de> for RawCommand {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct CommandVisitor;
+
+        impl<'de> Visitor<'de> for CommandVisitor {
+            type Value = RawCommand;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a command name or a mapping")
+            }
+
+            fn visit_str<E: de::Error>(self, name: &str) -> Result<Self::Value, E> {
+                Ok(RawCommand::Name(name.to_string()))
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+                DetailedCommand::deserialize(MapAccessDeserializer::new(map))
+                    .map(|command| RawCommand::Detailed(Box::new(command)))
+            }
+        }
+
+        deserializer.deserialize_any(CommandVisitor)
     }
+}
 
-    #[allow(dead_code)]
-    pub fn get_absolute_user_files(&self) -> &[PathBuf] {
-        self.absolute_user_files.as_slice()
+// Turns the raw config into the checked one, collecting every problem on the way.
+struct Checker<'a> {
+    positions: &'a Positions,
+    diagnostics: Vec<Diagnostic>,
+}
+
+fn join(path: &[String], key: &str) -> Vec<String> {
+    let mut path = path.to_vec();
+    path.push(key.to_string());
+    path
+}
+
+impl<'a> Checker<'a> {
+    fn error(&mut self, position: Position, message: String) {
+        self.diagnostics.push(Diagnostic::error(position, message));
     }
 
-    pub fn get_command_context(&self) -> CommandContext {
-        CommandContext::new(&self.workdir, self.absolute_user_files.as_slice())
+    fn warning(&mut self, position: Position, message: String) {
+        self.diagnostics
+            .push(Diagnostic::warning(position, message));
     }
 
-    fn get_matching_user_files(
+    fn check_unknown(&mut self, path: &[String], unknown: &Mapping) {
+        for key in unknown.keys() {
+            let key = match key.as_str() {
+                Some(key) => key.to_string(),
+                None => format!("{key:?}"),
+            };
+            let position = self.positions.key(&join(path, &key));
+            self.error(position, format!("unknown key \"{key}\""));
+        }
+    }
+
+    fn check_limits(&mut self, path: &[String], limits: &RawLimits) -> Limits {
+        let (limits, errors) = limits.parse();
+        for (key, err) in errors {
+            let position = self.positions.value(&join(path, key));
+            self.error(position, format!("invalid {key}: {err:#}"));
+        }
+        limits
+    }
+
+    fn check_benchmarks(
+        &mut self,
+        path: &[String],
+        benchmarks: BTreeMap<String, RawThreshold>,
+    ) -> Vec<Threshold> {
+        let mut checked = vec![];
+        for (name, raw) in benchmarks {
+            let path = join(path, &name);
+            self.check_unknown(&path, &raw.unknown);
+            if raw.is_empty() {
+                let message = "benchmark has neither max-time nor max-ratio".to_string();
+                self.error(self.positions.key(&path), message);
+                continue;
+            }
+            let (threshold, errors) = raw.parse(&name);
+            for (key, err) in errors {
+                let position = self.positions.value(&join(&path, key));
+                self.error(position, format!("invalid {key}: {err:#}"));
+            }
+            checked.push(threshold);
+        }
+        checked
+    }
+
+    fn check_toolchain(&mut self, toolchain: RawToolchain) -> Option<Toolchain> {
+        let path = vec!["toolchain".to_string()];
+        let toolchain = match toolchain {
+            RawToolchain::Name(name) => {
+                return match Toolchain::from_name(&name) {
+                    Ok(toolchain) => Some(toolchain),
+                    Err(err) => {
+                        self.error(self.positions.value(&path), err.to_string());
+                        None
+                    }
+                };
+            }
+            RawToolchain::Detailed(toolchain) => *toolchain,
+        };
+        self.check_unknown(&path, &toolchain.unknown);
+        let mut checked = match Toolchain::from_name(&toolchain.channel) {
+            Ok(checked) => checked,
+            Err(err) => {
+                let position = self.positions.value(&join(&path, "channel"));
+                self.error(position, err.to_string());
+                return None;
+            }
+        };
+        let mut valid = true;
+        for (index, name) in toolchain.components.iter().enumerate() {
+            if let Err(err) = checked.add_component(name) {
+                let position = self
+                    .positions
+                    .value(&join(&join(&path, "components"), &index.to_string()));
+                self.error(position, err.to_string());
+                valid = false;
+            }
+        }
+        for (index, name) in toolchain.targets.iter().enumerate() {
+            if let Err(err) = checked.add_target(name) {
+                let position = self
+                    .positions
+                    .value(&join(&join(&path, "targets"), &index.to_string()));
+                self.error(position, err.to_string());
+                valid = false;
+            }
+        }
+        valid.then_some(checked)
+    }
+
+    fn check_patterns(
+        &mut self,
         workdir: &Path,
-        patterns: &[&str],
-    ) -> Result<(Vec<PathBuf>, Vec<PathBuf>)> {
+        patterns: &[String],
+    ) -> (Vec<PathBuf>, Vec<PathBuf>) {
         let options = MatchOptions {
             case_sensitive: false,
             require_literal_separator: false,
@@ -97,15 +331,286 @@ impl Config {
         let workdir_len = workdir.iter().count();
         let mut relative_user_files = Vec::new();
         let mut absolute_user_files = Vec::new();
-        for pattern in patterns {
-            let pattern = workdir.join(pattern).to_path_buf();
-            let pattern = pattern.to_str().context("non-utf-8 path")?;
-            for entry in glob_with(pattern, options).context("pattern is invalid")? {
-                let entry = entry?;
-                absolute_user_files.push(entry.clone());
-                relative_user_files.push(entry.iter().skip(workdir_len).collect());
+        for (index, pattern) in patterns.iter().enumerate() {
+            let position = self
+                .positions
+                .value(&["allowed-patterns".to_string(), index.to_string()]);
+            let Some(full_pattern) = workdir.join(pattern).to_str().map(str::to_string) else {
+                self.error(position, format!("pattern \"{pattern}\" is not utf-8"));
+                continue;
+            };
+            let entries = match glob_with(&full_pattern, options) {
+                Ok(entries) => entries,
+                Err(err) => {
+                    self.error(
+                        position,
+                        format!("pattern \"{pattern}\" is invalid: {}", err.msg),
+                    );
+                    continue;
+                }
+            };
+            let mut matched = false;
+            for entry in entries {
+                matched = true;
+                let entry = match entry {
+                    Ok(entry) => entry,
+                    Err(err) => {
+                        self.error(position, format!("pattern \"{pattern}\": {err}"));
+                        continue;
+                    }
+                };
+                let relative: PathBuf = entry.iter().skip(workdir_len).collect();
+                if entry.is_dir() {
+                    self.error(
+                        position,
+                        format!(
+                            "pattern \"{pattern}\" matches directory \"{}\", only files are allowed",
+                            relative.display()
+                        ),
+                    );
+                    continue;
+                }
+                absolute_user_files.push(entry);
+                relative_user_files.push(relative);
+            }
+            if !matched {
+                self.warning(position, format!("pattern \"{pattern}\" matches nothing"));
+            }
+        }
+        (relative_user_files, absolute_user_files)
+    }
+
+    fn check_steps(&mut self, steps: RawSteps) -> Vec<Step> {
+        let steps_path = vec!["steps".to_string()];
+        if steps.0.is_empty() {
+            let position = self.positions.value(&steps_path);
+            self.warning(position, "there are no steps".to_string());
+        }
+        let mut checked = vec![];
+        let mut counts = HashMap::<String, usize>::new();
+        for (name, step) in steps.0 {
+            let path = join(&steps_path, &name);
+            let count = counts.entry(name.clone()).or_default();
+            *count += 1;
+            if *count > 1 {
+                let position = self.positions.duplicates(&path).get(*count - 2).copied();
+                let position = position.unwrap_or_else(|| self.positions.key(&path));
+                self.error(position, format!("duplicate step \"{name}\""));
+                continue;
+            }
+            let (commands, commands_path, limits) = match step {
+                RawStep::Commands(commands) => (commands, path.clone(), Limits::default()),
+                RawStep::Detailed(step) => {
+                    self.check_unknown(&path, &step.unknown);
+                    let limits = self.check_limits(&path, &step.limits);
+                    (step.commands, join(&path, "commands"), limits)
+                }
+            };
+            if commands.is_empty() {
+                let position = self.positions.key(&path);
+                self.warning(position, format!("step \"{name}\" has no commands"));
+            }
+            let commands = commands
+                .into_iter()
+                .enumerate()
+                .filter_map(|(index, command)| {
+                    self.check_command(&join(&commands_path, &index.to_string()), command, limits)
+                })
+                .collect();
+            checked.push(Step::new(name, commands));
+        }
+        checked
+    }
+
+    fn check_command(
+        &mut self,
+        path: &[String],
+        command: RawCommand,
+        step_limits: Limits,
+    ) -> Option<StepCommand> {
+        let command = match command {
+            RawCommand::Name(name) => {
+                return match Command::from_name(&name) {
+                    Ok(Command::CompileFail(_)) => {
+                        self.error(
+                            self.positions.value(path),
+                            "\"compile-fail\" needs \"features\" with the cases".to_string(),
+                        );
+                        None
+                    }
+                    Ok(command) => Some(StepCommand::new(command, step_limits)),
+                    Err(err) => {
+                        self.error(self.positions.value(path), err.to_string());
+                        None
+                    }
+                };
             }
+            RawCommand::Detailed(command) => *command,
+        };
+        self.check_unknown(path, &command.unknown);
+        let limits = self.check_limits(path, &command.limits).or(step_limits);
+        let Some(name) = command.name else {
+            let message = match command.program {
+                Some(_) => "custom command has no name",
+                None => "command has neither a program nor a name",
+            };
+            self.error(self.positions.value(path), message.to_string());
+            return None;
+        };
+        if command.benchmarks.is_some() && (name != "cargo-bench" || command.program.is_some()) {
+            let position = self.positions.key(&join(path, "benchmarks"));
+            self.error(
+                position,
+                "\"benchmarks\" is allowed only in cargo-bench".to_string(),
+            );
+        }
+        let command = match command.program {
+            Some(program) => Command::Custom(CustomCommand::new(
+                name,
+                program,
+                command.args.unwrap_or_default(),
+                command.env.unwrap_or_default(),
+                command.workdir,
+                command.expect.unwrap_or_default(),
+                command.features.unwrap_or_default(),
+            )),
+            None => {
+                let compile_fail = name == "compile-fail";
+                let custom_keys = [
+                    ("args", command.args.is_some()),
+                    ("env", command.env.is_some()),
+                    ("workdir", command.workdir.is_some()),
+                    ("expect", command.expect.is_some()),
+                    ("features", command.features.is_some() && !compile_fail),
+                ];
+                for (key, _) in custom_keys.iter().filter(|(_, present)| *present) {
+                    let position = self.positions.key(&join(path, key));
+                    self.error(
+                        position,
+                        format!("\"{key}\" is allowed only in custom commands with a program"),
+                    );
+                }
+                match Command::from_name(&name) {
+                    Ok(Command::CompileFail(_)) => match command.features {
+                        Some(features) if !features.is_empty() => Command::CompileFail(features),
+                        _ => {
+                            self.error(
+                                self.positions.value(path),
+                                "\"compile-fail\" needs \"features\" with the cases".to_string(),
+                            );
+                            return None;
+                        }
+                    },
+                    Ok(Command::CargoBench(_)) => Command::CargoBench(self.check_benchmarks(
+                        &join(path, "benchmarks"),
+                        command.benchmarks.unwrap_or_default(),
+                    )),
+                    Ok(command) => command,
+                    Err(err) => {
+                        let position = self.positions.value(&join(path, "name"));
+                        self.error(position, err.to_string());
+                        return None;
+                    }
+                }
+            }
+        };
+        Some(StepCommand::new(command, limits))
+    }
+}
+
+impl Config {
+    pub fn from_yml(path: &Path) -> Result<Self> {
+        let (config, diagnostics) = Self::check_yml(path)?;
+        let errors: Vec<_> = diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.is_error())
+            .map(|diagnostic| format!("{}:{diagnostic}", path.display()))
+            .collect();
+        match config {
+            Some(config) if errors.is_empty() => Ok(config),
+            _ => bail!("invalid config:\n{}", errors.join("\n")),
         }
-        Ok((relative_user_files, absolute_user_files))
+    }
+
+    // Returns the config if it has no errors, and the diagnostics with positions in the file.
+    pub fn check_yml(path: &Path) -> Result<(Option<Self>, Vec<Diagnostic>)> {
+        let source = fs::read_to_string(path).context("no yml file with config")?;
+        let positions = match Positions::parse(&source) {
+            Ok(positions) => positions,
+            Err((position, message)) => {
+                return Ok((None, vec![Diagnostic::error(position, message)]));
+            }
+        };
+        let raw: RawConfig = match serde_yaml::from_str(&source) {
+            Ok(raw) => raw,
+            Err(err) => {
+                let position = err.location().map_or(Position::new(1, 1), |location| {
+                    Position::new(location.line(), location.column())
+                });
+                // The message of serde_yaml ends with the position that is reported separately.
+                let message = err.to_string();
+                let message = message.split(" at line ").next().unwrap_or_default();
+                return Ok((None, vec![Diagnostic::error(position, message.to_string())]));
+            }
+        };
+        let workdir = path.parent().context("yml has no parent")?.to_path_buf();
+        let mut checker = Checker {
+            positions: &positions,
+            diagnostics: vec![],
+        };
+        checker.check_unknown(&[], &raw.unknown);
+        let toolchain = checker.check_toolchain(raw.toolchain);
+        let (relative_user_files, absolute_user_files) =
+            checker.check_patterns(&workdir, &raw.allowed_patterns);
+        let steps = checker.check_steps(raw.steps);
+        let mut diagnostics = checker.diagnostics;
+        diagnostics.sort_by_key(|diagnostic| diagnostic.position());
+        let config = match toolchain {
+            Some(toolchain) if !diagnostics.iter().any(Diagnostic::is_error) => Some(Self {
+                workdir,
+                toolchain,
+                relative_user_files,
+                absolute_user_files,
+                steps,
+                scoring: raw.scoring,
+                sandbox: raw.sandbox,
+            }),
+            _ => None,
+        };
+        Ok((config, diagnostics))
+    }
+
+    pub fn get_workdir(&self) -> &Path {
+        &self.workdir
+    }
+
+    pub fn get_steps(&self) -> &[Step] {
+        self.steps.as_slice()
+    }
+
+    pub fn get_scoring(&self) -> &Scoring {
+        &self.scoring
+    }
+
+    pub fn get_toolchain(&self) -> &Toolchain {
+        &self.toolchain
+    }
+
+    pub fn get_relative_user_files(&self) -> &[PathBuf] {
+        self.relative_user_files.as_slice()
+    }
+
+    pub fn get_absolute_user_files(&self) -> &[PathBuf] {
+        self.absolute_user_files.as_slice()
+    }
+
+    pub fn get_command_context(&self) -> Result<CommandContext> {
+        let mut context = CommandContext::new(&self.workdir, self.absolute_user_files.as_slice());
+        let sandbox = self
+            .sandbox
+            .map(|sandbox| sandbox.prepare(&self.workdir, &self.toolchain))
+            .transpose()?;
+        context.set_sandbox(sandbox);
+        Ok(context)
     }
 }
@@ -1,8 +1,19 @@
-use std::path::{Path, PathBuf};
+use super::sandbox::PreparedSandbox;
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
 
 pub struct CommandContext {
     workdir: PathBuf,
     user_files: Vec<PathBuf>,
+    echo_output: bool,
+    test_filter: Option<String>,
+    cancel: Option<Arc<AtomicBool>>,
+    sandbox: Option<PreparedSandbox>,
 }
 
 impl CommandContext {
@@ -10,6 +21,10 @@ impl CommandContext {
         Self {
             workdir: workdir.to_path_buf(),
             user_files: user_files.to_vec(),
+            echo_output: true,
+            test_filter: None,
+            cancel: None,
+            sandbox: None,
         }
     }
 
@@ -20,4 +35,95 @@ impl CommandContext {
     pub fn get_user_files(&self) -> &[PathBuf] {
         &self.user_files
     }
+
+    pub fn set_echo_output(&mut self, echo_output: bool) {
+        self.echo_output = echo_output;
+    }
+
+    pub fn echo_output(&self) -> bool {
+        self.echo_output
+    }
+
+    pub fn set_test_filter(&mut self, test_filter: Option<String>) {
+        self.test_filter = test_filter;
+    }
+
+    // Passed to cargo test to run only the matching tests.
+    pub fn test_filter(&self) -> Option<&str> {
+        self.test_filter.as_deref()
+    }
+
+    pub fn set_cancel(&mut self, cancel: Option<Arc<AtomicBool>>) {
+        self.cancel = cancel;
+    }
+
+    pub fn get_cancel(&self) -> Option<&AtomicBool> {
+        self.cancel.as_deref()
+    }
+
+    pub fn set_sandbox(&mut self, sandbox: Option<PreparedSandbox>) {
+        self.sandbox = sandbox;
+    }
+
+    pub fn get_sandbox(&self) -> Option<&PreparedSandbox> {
+        self.sandbox.as_ref()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel
+            .as_ref()
+            .is_some_and(|cancel| cancel.load(Ordering::SeqCst))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct LaunchOptions {
+    echo_output: bool,
+    jobs: usize,
+    use_cache: bool,
+    test_filter: Option<String>,
+    cancel: Option<Arc<AtomicBool>>,
+}
+
+impl LaunchOptions {
+    pub fn new(echo_output: bool, jobs: usize, use_cache: bool) -> Self {
+        Self {
+            echo_output,
+            jobs: jobs.max(1),
+            use_cache,
+            test_filter: None,
+            cancel: None,
+        }
+    }
+
+    pub fn echo_output(&self) -> bool {
+        self.echo_output
+    }
+
+    pub fn jobs(&self) -> usize {
+        self.jobs
+    }
+
+    pub fn use_cache(&self) -> bool {
+        self.use_cache
+    }
+
+    pub fn with_test_filter(mut self, test_filter: Option<&str>) -> Self {
+        self.test_filter = test_filter.map(str::to_string);
+        self
+    }
+
+    pub fn test_filter(&self) -> Option<&str> {
+        self.test_filter.as_deref()
+    }
+
+    // Once the flag is set, running commands are killed and the rest are skipped.
+    pub fn with_cancel(mut self, cancel: Arc<AtomicBool>) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    pub fn cancel(&self) -> Option<&Arc<AtomicBool>> {
+        self.cancel.as_ref()
+    }
 }
@@ -0,0 +1,67 @@
+use super::positions::Position;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Warning => "warning",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Diagnostic {
+    severity: Severity,
+    position: Position,
+    message: String,
+}
+
+impl Diagnostic {
+    pub fn error(position: Position, message: String) -> Self {
+        Self {
+            severity: Severity::Error,
+            position,
+            message,
+        }
+    }
+
+    pub fn warning(position: Position, message: String) -> Self {
+        Self {
+            severity: Severity::Warning,
+            position,
+            message,
+        }
+    }
+
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}: {}",
+            self.position.line,
+            self.position.column,
+            self.severity.name(),
+            self.message
+        )
+    }
+}
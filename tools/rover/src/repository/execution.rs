@@ -0,0 +1,158 @@
+use super::limits::{LimitExceeded, Limits};
+use anyhow::{bail, Context, Result};
+use std::{
+    fs,
+    io::Read,
+    os::unix::process::CommandExt,
+    process::{self, ExitStatus, Stdio},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+const READ_CHUNK_SIZE: usize = 8192;
+
+pub struct Execution {
+    pub status: ExitStatus,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+    pub exceeded: Option<LimitExceeded>,
+}
+
+// Kills the process group as soon as the cancel flag is set.
+pub fn execute(
+    cmd: &mut process::Command,
+    limits: &Limits,
+    cancel: Option<&AtomicBool>,
+) -> Result<Execution> {
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .process_group(0)
+        .spawn()
+        .context("failed to spawn process")?;
+    let group = child.id() as i32;
+    let written = Arc::new(AtomicU64::new(0));
+    let stdout = spawn_reader(child.stdout.take(), written.clone(), limits.max_output());
+    let stderr = spawn_reader(child.stderr.take(), written.clone(), limits.max_output());
+
+    let start = Instant::now();
+    let mut exceeded = None;
+    let mut cancelled = false;
+    let status = loop {
+        if let Some(status) = child.try_wait().context("failed to wait for process")? {
+            break status;
+        }
+        cancelled = cancel.is_some_and(|cancel| cancel.load(Ordering::SeqCst));
+        if cancelled {
+            kill_group(group);
+            break child.wait().context("failed to wait for killed process")?;
+        }
+        exceeded = exceeded.or_else(|| check_limits(limits, start, &written, group));
+        if exceeded.is_some() {
+            kill_group(group);
+            break child.wait().context("failed to wait for killed process")?;
+        }
+        thread::sleep(POLL_INTERVAL);
+    };
+    // The readers finish once every process of the group closes the pipes.
+    kill_group(group);
+    let stdout = stdout.join().expect("stdout reader panicked");
+    let stderr = stderr.join().expect("stderr reader panicked");
+    if cancelled {
+        bail!("command was cancelled");
+    }
+    if exceeded.is_none() {
+        exceeded = limits
+            .max_output()
+            .filter(|limit| written.load(Ordering::SeqCst) > *limit)
+            .map(LimitExceeded::OutputLimit);
+    }
+    Ok(Execution {
+        status,
+        stdout,
+        stderr,
+        exceeded,
+    })
+}
+
+fn spawn_reader(
+    pipe: Option<impl Read + Send + 'static>,
+    written: Arc<AtomicU64>,
+    max_output: Option<u64>,
+) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut output = Vec::new();
+        let Some(mut pipe) = pipe else {
+            return output;
+        };
+        let mut chunk = [0; READ_CHUNK_SIZE];
+        while let Ok(read) = pipe.read(&mut chunk) {
+            if read == 0 {
+                break;
+            }
+            let total = written.fetch_add(read as u64, Ordering::SeqCst) + read as u64;
+            // Keep draining the pipe over the limit, so the process is not blocked before it's killed.
+            if max_output.is_none_or(|limit| total <= limit) {
+                output.extend_from_slice(&chunk[..read]);
+            }
+        }
+        output
+    })
+}
+
+fn check_limits(
+    limits: &Limits,
+    start: Instant,
+    written: &AtomicU64,
+    group: i32,
+) -> Option<LimitExceeded> {
+    if let Some(timeout) = limits.timeout() {
+        if start.elapsed() > timeout {
+            return Some(LimitExceeded::TimedOut(timeout));
+        }
+    }
+    if let Some(limit) = limits.max_output() {
+        if written.load(Ordering::SeqCst) > limit {
+            return Some(LimitExceeded::OutputLimit(limit));
+        }
+    }
+    if let Some(limit) = limits.memory_limit() {
+        if group_memory(group) > limit {
+            return Some(LimitExceeded::MemoryLimit(limit));
+        }
+    }
+    None
+}
+
+fn kill_group(group: i32) {
+    // SAFETY: kill has no memory safety preconditions. The group is created by us and the error
+    // is ignored on purpose: the group may be gone already.
+    unsafe {
+        libc::kill(-group, libc::SIGKILL);
+    }
+}
+
+// Sums resident memory of all the processes in the group. Works only on Linux, where /proc exists.
+fn group_memory(group: i32) -> u64 {
+    // SAFETY: sysconf has no memory safety preconditions.
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) }.max(0) as u64;
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return 0;
+    };
+    entries
+        .filter_map(|entry| {
+            let stat = fs::read_to_string(entry.ok()?.path().join("stat")).ok()?;
+            // The second field is the executable name in parens that may contain spaces.
+            let fields: Vec<_> = stat.get(stat.rfind(')')? + 2..)?.split(' ').collect();
+            let process_group: i32 = fields.get(2)?.parse().ok()?;
+            let rss_pages: u64 = fields.get(21)?.parse().ok()?;
+            (process_group == group).then_some(rss_pages * page_size)
+        })
+        .sum()
+}
@@ -0,0 +1,321 @@
+use anyhow::{Context, Result};
+use proc_macro2::{Span, TokenStream, TokenTree};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+use syn::{
+    visit::{self, Visit},
+    Attribute, File, Item, ItemUse, Macro, Meta, UseTree,
+};
+
+const FORBIDDEN_COLLECTIONS: [&str; 8] = [
+    "BTreeMap",
+    "BTreeSet",
+    "HashMap",
+    "HashSet",
+    "Vec",
+    "VecDeque",
+    "LinkedList",
+    "BinaryHeap",
+];
+const STD_CRATES: [&str; 2] = ["std", "alloc"];
+
+pub struct Violation {
+    file: PathBuf,
+    line: usize,
+    column: usize,
+    message: String,
+}
+
+impl Violation {
+    fn new(file: &Path, span: Span, message: String) -> Self {
+        let start = span.start();
+        Self {
+            file: file.to_path_buf(),
+            line: start.line.max(1),
+            column: start.column + 1,
+            message,
+        }
+    }
+}
+
+impl Violation {
+    pub fn with_file(self, file: &Path) -> Self {
+        Self {
+            file: file.to_path_buf(),
+            ..self
+        }
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}: {}",
+            self.file.display(),
+            self.line,
+            self.column,
+            self.message
+        )
+    }
+}
+
+fn parse(path: &Path) -> Result<std::result::Result<File, Violation>> {
+    let source = fs::read_to_string(path).with_context(|| format!("failed to read {path:?}"))?;
+    Ok(syn::parse_file(&source)
+        .map_err(|err| Violation::new(path, err.span(), format!("failed to parse file: {err}"))))
+}
+
+fn has_inner_attribute(attrs: &[Attribute], name: &str, argument: Option<&str>) -> bool {
+    attrs.iter().any(|attr| match (&attr.meta, argument) {
+        (Meta::Path(path), None) => path.is_ident(name),
+        (Meta::List(list), Some(argument)) => {
+            list.path.is_ident(name)
+                && list
+                    .tokens
+                    .clone()
+                    .into_iter()
+                    .any(|token| matches!(token, TokenTree::Ident(ident) if ident == argument))
+        }
+        _ => false,
+    })
+}
+
+pub fn check_forbid_unsafe(path: &Path) -> Result<Vec<Violation>> {
+    let file = match parse(path)? {
+        Ok(file) => file,
+        Err(violation) => return Ok(vec![violation]),
+    };
+    if has_inner_attribute(&file.attrs, "forbid", Some("unsafe_code")) {
+        Ok(vec![])
+    } else {
+        Ok(vec![Violation::new(
+            path,
+            Span::call_site(),
+            "file does not contain '#![forbid(unsafe_code)]' attribute".to_string(),
+        )])
+    }
+}
+
+pub fn check_forbid_std(path: &Path) -> Result<Vec<Violation>> {
+    let file = match parse(path)? {
+        Ok(file) => file,
+        Err(violation) => return Ok(vec![violation]),
+    };
+    let mut violations = vec![];
+    if !has_inner_attribute(&file.attrs, "no_std", None) {
+        violations.push(Violation::new(
+            path,
+            Span::call_site(),
+            "file does not contain '#![no_std]' attribute".to_string(),
+        ));
+    }
+    for item in &file.items {
+        if let Item::ExternCrate(item) = item {
+            if item.ident == "std" {
+                violations.push(Violation::new(
+                    path,
+                    item.ident.span(),
+                    "'extern crate std' is forbidden".to_string(),
+                ));
+            }
+        }
+    }
+    Ok(violations)
+}
+
+pub fn check_forbid_collections(path: &Path) -> Result<Vec<Violation>> {
+    let file = match parse(path)? {
+        Ok(file) => file,
+        Err(violation) => return Ok(vec![violation]),
+    };
+    let mut visitor = CollectionsVisitor {
+        path,
+        aliases: HashMap::new(),
+        local_items: HashSet::new(),
+        violations: vec![],
+    };
+    visitor.collect_names(&file);
+    visitor.visit_file(&file);
+    Ok(visitor.violations)
+}
+
+struct CollectionsVisitor<'a> {
+    path: &'a Path,
+    aliases: HashMap<String, Vec<String>>,
+    local_items: HashSet<String>,
+    violations: Vec<Violation>,
+}
+
+impl<'a> CollectionsVisitor<'a> {
+    fn collect_names(&mut self, file: &File) {
+        struct Collector<'c, 'a>(&'c mut CollectionsVisitor<'a>);
+
+        impl<'ast, 'c, 'a> Visit<'ast> for Collector<'c, 'a> {
+            fn visit_item_use(&mut self, item: &'ast ItemUse) {
+                let mut aliases = vec![];
+                flatten_use_tree(&item.tree, vec![], &mut aliases);
+                for (alias, path, _) in aliases {
+                    if let Some(alias) = alias {
+                        self.0.aliases.insert(alias, path);
+                    }
+                }
+            }
+
+            fn visit_item(&mut self, item: &'ast Item) {
+                let ident = match item {
+                    Item::Struct(item) => Some(&item.ident),
+                    Item::Enum(item) => Some(&item.ident),
+                    Item::Union(item) => Some(&item.ident),
+                    Item::Type(item) => Some(&item.ident),
+                    Item::Trait(item) => Some(&item.ident),
+                    Item::Macro(item) => item.ident.as_ref(),
+                    _ => None,
+                };
+                if let Some(ident) = ident {
+                    self.0.local_items.insert(ident.to_string());
+                }
+                visit::visit_item(self, item);
+            }
+        }
+
+        Collector(self).visit_file(file);
+    }
+
+    fn resolve(&self, segments: &[String]) -> Option<Vec<String>> {
+        let (first, rest) = segments.split_first()?;
+        if let Some(path) = self.aliases.get(first) {
+            return Some(path.iter().chain(rest).cloned().collect());
+        }
+        if first == "Vec" && !self.local_items.contains(first) {
+            let prelude = ["std", "vec", "Vec"].map(String::from);
+            return Some(prelude.iter().chain(rest).cloned().collect());
+        }
+        Some(segments.to_vec())
+    }
+
+    fn check(&mut self, segments: &[String], span: Span) {
+        if let Some(resolved) = self.resolve(segments) {
+            if is_forbidden(&resolved) {
+                self.violations.push(Violation::new(
+                    self.path,
+                    span,
+                    format!("usage of '{}' is forbidden", resolved.join("::")),
+                ));
+            }
+        }
+    }
+
+    fn check_tokens(&mut self, tokens: TokenStream) {
+        for token in tokens {
+            match token {
+                TokenTree::Ident(ident) => {
+                    let name = ident.to_string();
+                    if self.aliases.contains_key(&name) || name == "Vec" {
+                        self.check(&[name], ident.span());
+                    }
+                }
+                TokenTree::Group(group) => self.check_tokens(group.stream()),
+                _ => {}
+            }
+        }
+    }
+}
+
+impl<'ast, 'a> Visit<'ast> for CollectionsVisitor<'a> {
+    fn visit_path(&mut self, path: &'ast syn::Path) {
+        let segments: Vec<_> = path
+            .segments
+            .iter()
+            .map(|segment| segment.ident.to_string())
+            .collect();
+        if let Some(first) = path.segments.first() {
+            self.check(&segments, first.ident.span());
+        }
+        visit::visit_path(self, path);
+    }
+
+    fn visit_item_use(&mut self, item: &'ast ItemUse) {
+        let mut imports = vec![];
+        flatten_use_tree(&item.tree, vec![], &mut imports);
+        for (_, path, span) in imports {
+            if is_forbidden(&path) {
+                self.violations.push(Violation::new(
+                    self.path,
+                    span,
+                    format!("import of '{}' is forbidden", path.join("::")),
+                ));
+            }
+        }
+    }
+
+    fn visit_macro(&mut self, mac: &'ast Macro) {
+        if mac.path.is_ident("vec") && !self.local_items.contains("vec") {
+            self.violations.push(Violation::new(
+                self.path,
+                mac.path.segments[0].ident.span(),
+                "usage of 'vec!' is forbidden".to_string(),
+            ));
+        }
+        self.check_tokens(mac.tokens.clone());
+        visit::visit_macro(self, mac);
+    }
+}
+
+fn flatten_use_tree(
+    tree: &UseTree,
+    prefix: Vec<String>,
+    out: &mut Vec<(Option<String>, Vec<String>, Span)>,
+) {
+    match tree {
+        UseTree::Path(path) => {
+            let mut prefix = prefix;
+            prefix.push(path.ident.to_string());
+            flatten_use_tree(&path.tree, prefix, out);
+        }
+        UseTree::Name(name) => {
+            let mut path = prefix;
+            if name.ident != "self" {
+                path.push(name.ident.to_string());
+            }
+            let alias = path.last().cloned();
+            out.push((alias, path, name.ident.span()));
+        }
+        UseTree::Rename(rename) => {
+            let mut path = prefix;
+            if rename.ident != "self" {
+                path.push(rename.ident.to_string());
+            }
+            out.push((Some(rename.rename.to_string()), path, rename.ident.span()));
+        }
+        UseTree::Glob(glob) => {
+            let mut path = prefix;
+            path.push("*".to_string());
+            out.push((None, path, glob.star_token.span));
+        }
+        UseTree::Group(group) => {
+            for tree in &group.items {
+                flatten_use_tree(tree, prefix.clone(), out);
+            }
+        }
+    }
+}
+
+fn is_forbidden(path: &[String]) -> bool {
+    let Some(first) = path.first() else {
+        return false;
+    };
+    if !STD_CRATES.contains(&first.as_str()) {
+        return false;
+    }
+    match path.get(1).map(String::as_str) {
+        Some("collections") => path.len() > 2,
+        Some("vec") => path.get(2).is_some_and(|name| name == "Vec" || name == "*"),
+        _ => path
+            .last()
+            .is_some_and(|name| FORBIDDEN_COLLECTIONS.contains(&name.as_str())),
+    }
+}
@@ -0,0 +1,84 @@
+use super::cache::{problem_files, TARGET_FOLDER};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+pub const MANIFEST_NAME: &str = ".integrity.json";
+
+// Hashes of the files of a problem that students may not change, written by compose. Paths are
+// relative to the problem. The manifest is read from a checkout of the course that students
+// don't control, as the one next to a submission can be rewritten with it.
+#[derive(Default, Serialize, Deserialize)]
+pub struct Manifest {
+    files: BTreeMap<String, String>,
+}
+
+fn hash(content: &[u8]) -> String {
+    Sha256::digest(content)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+// Build outputs are neither protected nor checked.
+pub fn is_build_output(relative: &Path) -> bool {
+    relative.iter().any(|part| part == TARGET_FOLDER)
+}
+
+impl Manifest {
+    pub fn add(&mut self, path: &Path, content: &[u8]) {
+        self.files
+            .insert(path.to_string_lossy().replace('\\', "/"), hash(content));
+    }
+
+    pub fn to_json(&self) -> Result<Vec<u8>> {
+        let mut json = serde_json::to_vec_pretty(self).context("failed to serialize manifest")?;
+        json.push(b'\n');
+        Ok(json)
+    }
+
+    pub fn load(problem_path: &Path) -> Result<Self> {
+        let path = problem_path.join(MANIFEST_NAME);
+        let content = fs::read(&path).with_context(|| {
+            format!("failed to read integrity manifest {path:?}, it's created by rover compose")
+        })?;
+        serde_json::from_slice(&content)
+            .with_context(|| format!("invalid integrity manifest {path:?}"))
+    }
+
+    // The protected files that are modified or missing, and the files that are neither protected
+    // nor allowed, with the kind of change.
+    pub fn changed_files(
+        &self,
+        problem_path: &Path,
+        allowed: &[PathBuf],
+    ) -> Result<Vec<(String, &'static str)>> {
+        let mut changed = self
+            .files
+            .iter()
+            .filter_map(|(path, expected)| match fs::read(problem_path.join(path)) {
+                Ok(content) if hash(&content) == *expected => None,
+                Ok(_) => Some((path.clone(), "modified")),
+                Err(_) => Some((path.clone(), "missing")),
+            })
+            .collect::<Vec<_>>();
+        let mut files = vec![];
+        problem_files(problem_path, &mut files)?;
+        for file in files {
+            let relative = file.strip_prefix(problem_path)?;
+            let path = relative.to_string_lossy().replace('\\', "/");
+            if relative != Path::new(MANIFEST_NAME)
+                && !self.files.contains_key(&path)
+                && !allowed.iter().any(|file| file == relative)
+            {
+                changed.push((path, "added"));
+            }
+        }
+        Ok(changed)
+    }
+}
@@ -0,0 +1,169 @@
+use anyhow::{bail, Context, Error, Result};
+use serde::Deserialize;
+use serde_yaml::Value;
+use std::{fmt, time::Duration};
+
+const TIMEOUT_KEY: &str = "timeout";
+const MEMORY_LIMIT_KEY: &str = "memory-limit";
+const MAX_OUTPUT_KEY: &str = "max-output";
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Limits {
+    timeout: Option<Duration>,
+    memory_limit: Option<u64>,
+    max_output: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RawLimits {
+    timeout: Option<Value>,
+    memory_limit: Option<Value>,
+    max_output: Option<Value>,
+}
+
+impl RawLimits {
+    // Invalid limits are skipped and returned with their keys.
+    pub fn parse(&self) -> (Limits, Vec<(&'static str, Error)>) {
+        let mut errors = vec![];
+        let mut parse = |key, value: &Option<Value>, parse: fn(&Value) -> Result<u64>| {
+            let result = value.as_ref().map(parse).transpose();
+            result.unwrap_or_else(|err| {
+                errors.push((key, err));
+                None
+            })
+        };
+        let timeout = parse(TIMEOUT_KEY, &self.timeout, |value| {
+            parse_duration(value).map(|duration| duration.as_secs())
+        });
+        let limits = Limits {
+            timeout: timeout.map(Duration::from_secs),
+            memory_limit: parse(MEMORY_LIMIT_KEY, &self.memory_limit, parse_size),
+            max_output: parse(MAX_OUTPUT_KEY, &self.max_output, parse_size),
+        };
+        (limits, errors)
+    }
+}
+
+impl Limits {
+    pub fn or(self, other: Self) -> Self {
+        Self {
+            timeout: self.timeout.or(other.timeout),
+            memory_limit: self.memory_limit.or(other.memory_limit),
+            max_output: self.max_output.or(other.max_output),
+        }
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    pub fn memory_limit(&self) -> Option<u64> {
+        self.memory_limit
+    }
+
+    pub fn max_output(&self) -> Option<u64> {
+        self.max_output
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LimitExceeded {
+    TimedOut(Duration),
+    MemoryLimit(u64),
+    OutputLimit(u64),
+}
+
+impl LimitExceeded {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::TimedOut(_) => "timed-out",
+            Self::MemoryLimit(_) => "memory-limit",
+            Self::OutputLimit(_) => "output-limit",
+        }
+    }
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TimedOut(timeout) => write!(f, "timed out after {}s", timeout.as_secs()),
+            Self::MemoryLimit(limit) => {
+                write!(f, "exceeded memory limit of {}", format_size(*limit))
+            }
+            Self::OutputLimit(limit) => {
+                write!(f, "exceeded output limit of {}", format_size(*limit))
+            }
+        }
+    }
+}
+
+impl std::error::Error for LimitExceeded {}
+
+fn parse_duration(value: &Value) -> Result<Duration> {
+    let (number, multiplier) = match value.as_u64() {
+        Some(seconds) => (seconds, 1),
+        None => {
+            let text = value
+                .as_str()
+                .context("duration is neither a number nor a string")?;
+            let trimmed = text.trim();
+            let (number, multiplier) = match trimmed.char_indices().last() {
+                Some((pos, 's')) => (&trimmed[..pos], 1),
+                Some((pos, 'm')) => (&trimmed[..pos], 60),
+                Some((pos, 'h')) => (&trimmed[..pos], 60 * 60),
+                _ => (trimmed, 1),
+            };
+            let number = number.trim().parse().with_context(|| {
+                format!("\"{text}\" is not a duration like 90, \"90s\" or \"2m\"")
+            })?;
+            (number, multiplier)
+        }
+    };
+    if number == 0 {
+        bail!("duration must be positive")
+    }
+    let seconds = number
+        .checked_mul(multiplier)
+        .context("duration is too large")?;
+    Ok(Duration::from_secs(seconds))
+}
+
+fn parse_size(value: &Value) -> Result<u64> {
+    let (number, multiplier) = match value.as_u64() {
+        Some(bytes) => (bytes, 1),
+        None => {
+            let text = value
+                .as_str()
+                .context("size is neither a number nor a string")?;
+            let trimmed = text
+                .trim()
+                .trim_end_matches("iB")
+                .trim_end_matches('B')
+                .trim_end();
+            let (number, multiplier) = match trimmed.char_indices().last() {
+                Some((pos, 'K' | 'k')) => (&trimmed[..pos], 1 << 10),
+                Some((pos, 'M' | 'm')) => (&trimmed[..pos], 1 << 20),
+                Some((pos, 'G' | 'g')) => (&trimmed[..pos], 1 << 30),
+                _ => (trimmed, 1),
+            };
+            let number = number.trim().parse().with_context(|| {
+                format!("\"{text}\" is not a size like 1024, \"512K\" or \"2G\"")
+            })?;
+            (number, multiplier)
+        }
+    };
+    if number == 0 {
+        bail!("size must be positive")
+    }
+    number.checked_mul(multiplier).context("size is too large")
+}
+
+fn format_size(bytes: u64) -> String {
+    match bytes {
+        bytes if bytes >= 1 << 30 && bytes % (1 << 30) == 0 => format!("{}G", bytes >> 30),
+        bytes if bytes >= 1 << 20 && bytes % (1 << 20) == 0 => format!("{}M", bytes >> 20),
+        bytes if bytes >= 1 << 10 && bytes % (1 << 10) == 0 => format!("{}K", bytes >> 10),
+        bytes => format!("{bytes} bytes"),
+    }
+}
@@ -1,8 +1,22 @@
+mod bench;
+mod cache;
 mod command;
+mod compile_fail;
 mod config;
-mod context;
+pub mod context;
 mod copying;
+pub mod diagnostic;
+mod execution;
+mod forbid;
+pub mod integrity;
+mod limits;
+pub mod outcome;
+mod positions;
 pub mod problem;
 pub mod repo;
+mod sandbox;
+pub mod scoring;
+pub mod settings;
+pub mod solutions;
 mod step;
 mod toolchain;
@@ -0,0 +1,245 @@
+use super::{execution::Execution, limits::LimitExceeded, scoring::Score};
+use anyhow::Error;
+use std::time::Duration;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+impl Status {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Passed => "passed",
+            Self::Failed => "failed",
+            Self::Skipped => "skipped",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct CommandOutcome {
+    name: String,
+    toolchain: String,
+    shell_line: Option<String>,
+    status: Status,
+    duration: Duration,
+    exit_code: Option<i32>,
+    stdout: String,
+    stderr: String,
+    failure: Option<String>,
+    exceeded: Option<LimitExceeded>,
+    cached: bool,
+}
+
+impl CommandOutcome {
+    pub fn new(name: &str, toolchain: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            toolchain: toolchain.to_string(),
+            shell_line: None,
+            status: Status::Skipped,
+            duration: Duration::ZERO,
+            exit_code: None,
+            stdout: String::new(),
+            stderr: String::new(),
+            failure: None,
+            exceeded: None,
+            cached: false,
+        }
+    }
+
+    pub fn cached(name: &str, toolchain: &str) -> Self {
+        Self {
+            status: Status::Passed,
+            cached: true,
+            ..Self::new(name, toolchain)
+        }
+    }
+
+    pub fn record(&mut self, shell_line: String, output: &Execution) {
+        self.shell_line = Some(match self.shell_line.take() {
+            Some(previous) => previous + "; " + &shell_line,
+            None => shell_line,
+        });
+        self.exit_code = output.status.code();
+        self.stdout += &String::from_utf8_lossy(&output.stdout);
+        self.stderr += &String::from_utf8_lossy(&output.stderr);
+    }
+
+    pub fn finish(&mut self, duration: Duration, result: Result<(), Error>) {
+        self.duration = duration;
+        match result {
+            Ok(()) => self.status = Status::Passed,
+            Err(err) => {
+                self.status = Status::Failed;
+                self.exceeded = err.downcast_ref::<LimitExceeded>().copied();
+                self.failure = Some(format!("{err:#}"));
+            }
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn toolchain(&self) -> &str {
+        &self.toolchain
+    }
+
+    pub fn shell_line(&self) -> Option<&str> {
+        self.shell_line.as_deref()
+    }
+
+    pub fn status(&self) -> Status {
+        self.status
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    pub fn exit_code(&self) -> Option<i32> {
+        self.exit_code
+    }
+
+    pub fn stdout(&self) -> &str {
+        &self.stdout
+    }
+
+    pub fn stderr(&self) -> &str {
+        &self.stderr
+    }
+
+    pub fn failure(&self) -> Option<&str> {
+        self.failure.as_deref()
+    }
+
+    pub fn failure_kind(&self) -> Option<&'static str> {
+        match (self.status, self.exceeded) {
+            (Status::Failed, Some(exceeded)) => Some(exceeded.kind()),
+            (Status::Failed, None) => Some("failed"),
+            _ => None,
+        }
+    }
+
+    pub fn is_cached(&self) -> bool {
+        self.cached
+    }
+}
+
+#[derive(Debug)]
+pub struct StepOutcome {
+    name: String,
+    commands: Vec<CommandOutcome>,
+}
+
+impl StepOutcome {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            commands: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, command: CommandOutcome) {
+        self.commands.push(command);
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn commands(&self) -> &[CommandOutcome] {
+        self.commands.as_slice()
+    }
+
+    pub fn status(&self) -> Status {
+        let has = |status| self.commands.iter().any(|command| command.status == status);
+        if has(Status::Failed) {
+            Status::Failed
+        } else if has(Status::Skipped) && !has(Status::Passed) {
+            Status::Skipped
+        } else {
+            Status::Passed
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.commands.iter().map(CommandOutcome::duration).sum()
+    }
+}
+
+#[derive(Debug)]
+pub struct TestOutcome {
+    problem: String,
+    steps: Vec<StepOutcome>,
+    error: Option<String>,
+    test_score: Option<Score>,
+}
+
+impl TestOutcome {
+    pub fn new(problem: &str) -> Self {
+        Self {
+            problem: problem.to_string(),
+            steps: Vec::new(),
+            error: None,
+            test_score: None,
+        }
+    }
+
+    pub fn errored(problem: &str, error: Error) -> Self {
+        Self {
+            error: Some(format!("{error:#}")),
+            ..Self::new(problem)
+        }
+    }
+
+    pub fn push(&mut self, step: StepOutcome) {
+        self.steps.push(step);
+    }
+
+    pub fn set_test_score(&mut self, test_score: Option<Score>) {
+        self.test_score = test_score;
+    }
+
+    pub fn problem(&self) -> &str {
+        &self.problem
+    }
+
+    pub fn steps(&self) -> &[StepOutcome] {
+        self.steps.as_slice()
+    }
+
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    pub fn failed(&self) -> bool {
+        self.error.is_some()
+            || self
+                .steps
+                .iter()
+                .any(|step| step.status() == Status::Failed)
+    }
+
+    pub fn test_score(&self) -> Option<&Score> {
+        self.test_score.as_ref()
+    }
+
+    pub fn score(&self) -> f64 {
+        if let Some(test_score) = &self.test_score {
+            test_score.value()
+        } else if self.failed() {
+            0.0
+        } else {
+            1.0
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.steps.iter().map(StepOutcome::duration).sum()
+    }
+}
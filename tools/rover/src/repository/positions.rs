@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+use yaml_rust2::{
+    parser::{Event, MarkedEventReceiver, Parser},
+    scanner::Marker,
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    pub fn new(line: usize, column: usize) -> Self {
+        Self { line, column }
+    }
+
+    fn from_marker(marker: Marker) -> Self {
+        Self::new(marker.line(), marker.col() + 1)
+    }
+}
+
+// Positions of the keys and values of a YAML document by their path, where the path is made of
+// mapping keys and sequence indices.
+#[derive(Default)]
+pub struct Positions {
+    keys: HashMap<Vec<String>, Position>,
+    values: HashMap<Vec<String>, Position>,
+    // Keys repeated in the same mapping, after the first one.
+    duplicates: HashMap<Vec<String>, Vec<Position>>,
+}
+
+enum Frame {
+    Mapping {
+        path: Vec<String>,
+        key: Option<(String, Position)>,
+    },
+    Sequence {
+        path: Vec<String>,
+        index: usize,
+    },
+    // Contents of complex keys are not tracked.
+    Ignored,
+}
+
+#[derive(Default)]
+struct Collector {
+    positions: Positions,
+    stack: Vec<Frame>,
+}
+
+impl MarkedEventReceiver for Collector {
+    fn on_event(&mut self, event: Event, marker: Marker) {
+        let position = Position::from_marker(marker);
+        let is_node = matches!(
+            event,
+            Event::Scalar(..)
+                | Event::Alias(_)
+                | Event::SequenceStart(..)
+                | Event::MappingStart(..)
+        );
+        if !is_node {
+            if matches!(event, Event::SequenceEnd | Event::MappingEnd) {
+                self.stack.pop();
+            }
+            return;
+        }
+        let path = match self.stack.last_mut() {
+            None => Some(vec![]),
+            Some(Frame::Mapping { path, key }) => match key.take() {
+                Some((key, key_position)) => {
+                    let mut path = path.clone();
+                    path.push(key);
+                    if self.positions.keys.contains_key(&path) {
+                        let duplicates = self.positions.duplicates.entry(path.clone());
+                        duplicates.or_default().push(key_position);
+                    }
+                    self.positions.keys.insert(path.clone(), key_position);
+                    Some(path)
+                }
+                None => {
+                    let name = match &event {
+                        Event::Scalar(name, ..) => name.clone(),
+                        _ => String::new(),
+                    };
+                    *key = Some((name, position));
+                    None
+                }
+            },
+            Some(Frame::Sequence { path, index }) => {
+                let mut path = path.clone();
+                path.push(index.to_string());
+                *index += 1;
+                Some(path)
+            }
+            Some(Frame::Ignored) => None,
+        };
+        let Some(path) = path else {
+            if matches!(event, Event::SequenceStart(..) | Event::MappingStart(..)) {
+                self.stack.push(Frame::Ignored);
+            }
+            return;
+        };
+        self.positions.values.insert(path.clone(), position);
+        match event {
+            Event::MappingStart(..) => self.stack.push(Frame::Mapping { path, key: None }),
+            Event::SequenceStart(..) => self.stack.push(Frame::Sequence { path, index: 0 }),
+            _ => {}
+        }
+    }
+}
+
+impl Positions {
+    // Fails with the position and the description of a syntax error.
+    pub fn parse(source: &str) -> Result<Self, (Position, String)> {
+        let mut collector = Collector::default();
+        Parser::new_from_str(source)
+            .load(&mut collector, false)
+            .map_err(|err| (Position::from_marker(*err.marker()), err.info().to_string()))?;
+        Ok(collector.positions)
+    }
+
+    // The position of the key of a mapping entry, or of the value if it's not in a mapping.
+    // Falls back to the closest known parent.
+    pub fn key(&self, path: &[String]) -> Position {
+        self.keys
+            .get(path)
+            .copied()
+            .unwrap_or_else(|| self.value(path))
+    }
+
+    // The positions of the repeats of the key of a mapping entry, in the order of the document.
+    pub fn duplicates(&self, path: &[String]) -> &[Position] {
+        self.duplicates
+            .get(path)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    pub fn value(&self, path: &[String]) -> Position {
+        (0..=path.len())
+            .rev()
+            .find_map(|len| self.values.get(&path[..len]).copied())
+            .unwrap_or(Position::new(1, 1))
+    }
+}
@@ -1,13 +1,25 @@
+use super::cache::Cache;
 use super::config::Config;
+use super::context::{CommandContext, LaunchOptions};
+use super::diagnostic::Diagnostic;
+use super::integrity::Manifest;
+use super::outcome::{CommandOutcome, Status, StepOutcome, TestOutcome};
 use super::repo::PROBLEMS_FOLDER;
-use crate::{launch_git, repository::copying::copy_files};
-use anyhow::{bail, Result};
+use super::settings::Settings;
+use super::solutions::SolutionsRepo;
+use super::step::Step;
+use crate::repository::copying::copy_files;
+use anyhow::{bail, Context, Result};
 use std::{
     path::{Path, PathBuf},
-    process,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
 };
 
-const DEFAULT_YML_NAME: &str = ".config.yml";
+pub const DEFAULT_YML_NAME: &str = ".config.yml";
 
 pub struct Problem {
     path: PathBuf,
@@ -39,40 +51,176 @@ impl Problem {
         self.group() + "/" + &self.title()
     }
 
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
     pub fn relative_path(&self) -> PathBuf {
         PathBuf::from(PROBLEMS_FOLDER)
             .join(self.group())
             .join(self.title())
     }
 
+    pub fn config_path(&self) -> PathBuf {
+        self.path.join(DEFAULT_YML_NAME)
+    }
+
     pub fn config(&self) -> Result<Config> {
-        Config::from_yml(&self.path.join(DEFAULT_YML_NAME))
+        Config::from_yml(&self.config_path())
+    }
+
+    pub fn check_config(&self) -> Result<Vec<Diagnostic>> {
+        let (_, diagnostics) = Config::check_yml(&self.config_path())?;
+        Ok(diagnostics)
+    }
+
+    // Fails with the list of files that differ from the integrity manifest of the problem in the
+    // course checkout.
+    pub fn verify_integrity(&self, course: &Path) -> Result<()> {
+        let manifest = Manifest::load(&course.join(self.relative_path()))?;
+        let changed =
+            manifest.changed_files(&self.path, self.config()?.get_relative_user_files())?;
+        if changed.is_empty() {
+            return Ok(());
+        }
+        let list = changed
+            .iter()
+            .map(|(path, change)| format!("  {path} ({change})"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        bail!(
+            "protected files of {} differ from the course:\n{list}",
+            self.branch_name()
+        )
     }
 
-    pub fn launch_all_steps(&self) -> Result<()> {
+    pub fn launch_all_steps(&self, options: &LaunchOptions) -> Result<TestOutcome> {
+        self.launch_steps(options, None)
+    }
+
+    // Launches only the step with the given name if it's set.
+    pub fn launch_steps(&self, options: &LaunchOptions, only: Option<&str>) -> Result<TestOutcome> {
         let config = self.config()?;
+        if let Some(name) = only {
+            if !config.get_steps().iter().any(|step| step.name() == name) {
+                bail!("no step \"{name}\" in {:?}", self.config_path());
+            }
+        }
+        let steps = || {
+            config
+                .get_steps()
+                .iter()
+                .filter(|step| only.is_none_or(|name| step.name() == name))
+        };
+        config.get_toolchain().check_installed(
+            steps()
+                .flat_map(|step| step.commands())
+                .map(|command| command.command()),
+        )?;
+        let mut context = config.get_command_context()?;
+        context.set_echo_output(options.echo_output());
+        context.set_test_filter(options.test_filter().map(str::to_string));
+        context.set_cancel(options.cancel().cloned());
+        let mut cache = if options.use_cache() {
+            Some(Cache::load(&config)?)
+        } else {
+            None
+        };
+        let mut outcome = TestOutcome::new(&self.branch_name());
+        for step in steps() {
+            let step_outcome = if outcome.failed() || context.is_cancelled() {
+                let mut step_outcome = StepOutcome::new(step.name());
+                for command in step.commands() {
+                    step_outcome.push(CommandOutcome::new(
+                        command.command().name(),
+                        config.get_toolchain().name(),
+                    ));
+                }
+                step_outcome
+            } else {
+                Self::launch_step(&config, step, &context, cache.as_ref(), options.jobs())
+            };
+            if let Some(cache) = cache.as_mut() {
+                for (command, command_outcome) in
+                    step.commands().iter().zip(step_outcome.commands())
+                {
+                    match command_outcome.status() {
+                        // A filtered run has not passed the whole command.
+                        Status::Passed if context.test_filter().is_some() => {}
+                        Status::Passed => cache.store(step.name(), command.command()),
+                        Status::Failed => cache.invalidate(step.name(), command.command()),
+                        Status::Skipped => {}
+                    }
+                }
+            }
+            outcome.push(step_outcome);
+        }
+        if let Some(cache) = cache {
+            cache.save()?;
+        }
+        outcome.set_test_score(config.get_scoring().score(&outcome));
+        Ok(outcome)
+    }
+
+    fn launch_step(
+        config: &Config,
+        step: &Step,
+        context: &CommandContext,
+        cache: Option<&Cache>,
+        jobs: usize,
+    ) -> StepOutcome {
         let toolchain = config.get_toolchain();
-        let context = config.get_command_context();
-        for step in config.get_steps() {
-            for command in step.commands() {
-                toolchain.run_command(command, &context)?;
+        let commands = step.commands();
+        let next = AtomicUsize::new(0);
+        let failed = AtomicBool::new(false);
+        let outcomes = Mutex::new((0..commands.len()).map(|_| None).collect::<Vec<_>>());
+        thread::scope(|scope| {
+            for _ in 0..jobs.min(commands.len()) {
+                scope.spawn(|| loop {
+                    let index = next.fetch_add(1, Ordering::SeqCst);
+                    let Some(step_command) = commands.get(index) else {
+                        break;
+                    };
+                    let command = step_command.command();
+                    let command_outcome = if failed.load(Ordering::SeqCst) || context.is_cancelled()
+                    {
+                        CommandOutcome::new(command.name(), toolchain.name())
+                    } else if cache.is_some_and(|cache| cache.is_fresh(step.name(), command)) {
+                        CommandOutcome::cached(command.name(), toolchain.name())
+                    } else {
+                        toolchain.run_command(command, step_command.limits(), context)
+                    };
+                    if command_outcome.status() == Status::Failed {
+                        failed.store(true, Ordering::SeqCst);
+                    }
+                    outcomes.lock().unwrap()[index] = Some(command_outcome);
+                });
             }
+        });
+        let mut step_outcome = StepOutcome::new(step.name());
+        for command_outcome in outcomes.into_inner().unwrap().into_iter().flatten() {
+            step_outcome.push(command_outcome);
         }
-        Ok(())
+        step_outcome
     }
 
     pub fn move_solution_files_from(
         &self,
         solutions_repo: &Path,
         checkout_branch: bool,
+        settings: &Settings,
     ) -> Result<()> {
         let config = self.config()?;
         let relative_path = self.relative_path();
-        let branch_name = self.branch_name();
+        let branch_name = settings.branch_name(self);
         let solutions_problem_path = solutions_repo.join(relative_path);
         let repository_problem_path = self.path.clone();
-        if checkout_branch && !launch_git!(solutions_repo, "checkout", &branch_name) {
-            bail!("failed to checkout branch in solutions repository")
+        if checkout_branch {
+            SolutionsRepo::open(solutions_repo)?
+                .checkout_branch(&branch_name, settings.remote())
+                .with_context(|| {
+                    format!("failed to checkout {branch_name} in solutions repository")
+                })?;
         }
         copy_files(
             &solutions_problem_path,
@@ -82,30 +230,17 @@ impl Problem {
         )
     }
 
-    pub fn move_solution_files_to(&self, solutions_repo: &Path) -> Result<()> {
+    pub fn move_solution_files_to(&self, solutions_repo: &Path, settings: &Settings) -> Result<()> {
         let config = self.config()?;
         let relative_path = self.relative_path();
-        let branch_name = self.branch_name();
+        let branch_name = settings.branch_name(self);
         let solutions_problem_path = solutions_repo.join(relative_path);
         let repository_problem_path = self.path.clone();
-        if !launch_git!(solutions_repo, "checkout", &branch_name)
-            && !launch_git!(
-                solutions_repo,
-                "checkout",
-                "-b",
-                &branch_name,
-                "origin/master"
-            )
-            && !launch_git!(
-                solutions_repo,
-                "checkout",
-                "-b",
-                &branch_name,
-                "origin/main"
-            )
-        {
-            bail!("failed to both checkout and create branch in solutions repository")
-        }
+        SolutionsRepo::open(solutions_repo)?
+            .checkout_or_create(&branch_name, settings.remote(), &settings.start_points())
+            .with_context(|| {
+                format!("failed to checkout or create {branch_name} in solutions repository")
+            })?;
         copy_files(
             &repository_problem_path,
             &solutions_problem_path,
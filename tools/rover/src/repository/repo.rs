@@ -1,15 +1,20 @@
-use super::problem::Problem;
+use super::{
+    problem::{Problem, DEFAULT_YML_NAME},
+    settings::Settings,
+};
 use crate::compose;
 use anyhow::{bail, Context, Result};
-use std::path::{Path, PathBuf};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
 pub const COMPOSE_CONFIG: &str = ".compose.yml";
 pub const PROBLEMS_FOLDER: &str = "problems";
-pub const SOLUTIONS_REPO_FOLDER: &str = "solutions";
-pub const REPOSITORY_NAMES: [&str; 2] = ["mipt-rust", "mipt-rust-private"];
 
 pub struct Repository {
     path: PathBuf,
+    settings: Settings,
 }
 
 impl Repository {
@@ -17,15 +22,10 @@ impl Repository {
         let path = path
             .canonicalize()
             .context("cannot canonicalize path for repository")?;
-        let prefix_count = path
-            .iter()
-            .position(|comp| REPOSITORY_NAMES.iter().any(|name| *name == comp));
-        if let Some(pos) = prefix_count {
-            Ok(Self {
-                path: path.iter().take(pos + 1).collect(),
-            })
-        } else {
-            bail!("path does not contain course repository")
+        let settings = Settings::load()?;
+        match settings.find_root(&path) {
+            Some(path) => Ok(Self { path, settings }),
+            None => bail!("path does not contain course repository"),
         }
     }
 
@@ -54,12 +54,43 @@ impl Repository {
         }
     }
 
+    pub fn problems(&self, group: Option<&str>) -> Result<Vec<Problem>> {
+        let problems_path = self.path.join(PROBLEMS_FOLDER);
+        let mut problems = vec![];
+        for group_entry in fs::read_dir(&problems_path)
+            .with_context(|| format!("failed to read directory {problems_path:?}"))?
+        {
+            let group_path = group_entry.context("failed to read group entry")?.path();
+            if !group_path.is_dir()
+                || group.is_some_and(|group| group_path.file_name() != Some(group.as_ref()))
+            {
+                continue;
+            }
+            for problem_entry in fs::read_dir(&group_path)
+                .with_context(|| format!("failed to read directory {group_path:?}"))?
+            {
+                let problem_path = problem_entry
+                    .context("failed to read problem entry")?
+                    .path();
+                if problem_path.join(DEFAULT_YML_NAME).is_file() {
+                    problems.push(Problem::from_path(&problem_path));
+                }
+            }
+        }
+        if problems.is_empty() {
+            bail!("no problems found in {problems_path:?}")
+        }
+        problems.sort_by_key(Problem::branch_name);
+        Ok(problems)
+    }
+
     pub fn solutions_repo(&self) -> Result<PathBuf> {
-        let path = self.path.parent().unwrap().join(SOLUTIONS_REPO_FOLDER);
+        let path = self.settings.solutions_repo(&self.path);
         if path.is_dir() {
-            Ok(path)
+            path.canonicalize()
+                .context("failed to canonicalize solutions repository path")
         } else {
-            bail!("solutions repository does not exist")
+            bail!("solutions repository {path:?} does not exist")
         }
     }
 
@@ -67,8 +98,11 @@ impl Repository {
         compose::config::Config::from_yml(&self.path.join(COMPOSE_CONFIG))
     }
 
-    #[allow(unused)]
     pub fn get_path(&self) -> &Path {
         &self.path
     }
+
+    pub fn get_settings(&self) -> &Settings {
+        &self.settings
+    }
 }
@@ -0,0 +1,243 @@
+use super::toolchain::Toolchain;
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::{
+    env,
+    fs::{self, File},
+    io::{Seek, SeekFrom, Write},
+    os::{
+        fd::{AsRawFd, FromRawFd},
+        unix::process::CommandExt,
+    },
+    path::{Path, PathBuf},
+    process,
+};
+
+const BWRAP: &str = "bwrap";
+const TMP_DIR: &str = "/tmp";
+const TARGET_FOLDER: &str = "target";
+const MANIFEST: &str = "Cargo.toml";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Network {
+    // No sockets of internet families at all.
+    #[default]
+    None,
+    // A private network namespace with only the loopback interface.
+    Loopback,
+    Full,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Sandbox {
+    network: Network,
+}
+
+#[cfg(target_arch = "x86_64")]
+const AUDIT_ARCH: Option<u32> = Some(0xc000_003e);
+#[cfg(target_arch = "aarch64")]
+const AUDIT_ARCH: Option<u32> = Some(0xc000_00b7);
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+const AUDIT_ARCH: Option<u32> = None;
+
+const BPF_LD_W_ABS: u16 = 0x20;
+const BPF_JEQ_K: u16 = 0x15;
+const BPF_JGE_K: u16 = 0x35;
+const BPF_RET_K: u16 = 0x06;
+const SECCOMP_RET_ALLOW: u32 = 0x7fff_0000;
+const SECCOMP_RET_ERRNO: u32 = 0x0005_0000;
+// Syscalls of the x32 ABI have this bit set and other numbers.
+const X32_SYSCALL_BIT: u32 = 0x4000_0000;
+// Offsets in `struct seccomp_data`, the first argument is little-endian on supported arches.
+const NR_OFFSET: u32 = 0;
+const ARCH_OFFSET: u32 = 4;
+const FIRST_ARG_OFFSET: u32 = 16;
+
+// Seccomp filter denying `socket` for AF_INET and AF_INET6, as classic BPF for `bwrap --seccomp`.
+fn deny_inet_filter(arch: u32) -> Vec<u8> {
+    let deny = SECCOMP_RET_ERRNO | libc::EACCES as u32;
+    // (code, jump if true, jump if false, constant), jumps are relative to the next instruction.
+    let program: [(u16, u8, u8, u32); 11] = [
+        (BPF_LD_W_ABS, 0, 0, ARCH_OFFSET),
+        (BPF_JEQ_K, 1, 0, arch),
+        (BPF_RET_K, 0, 0, deny),
+        (BPF_LD_W_ABS, 0, 0, NR_OFFSET),
+        (BPF_JGE_K, 5, 0, X32_SYSCALL_BIT),
+        (BPF_JEQ_K, 0, 3, libc::SYS_socket as u32),
+        (BPF_LD_W_ABS, 0, 0, FIRST_ARG_OFFSET),
+        (BPF_JEQ_K, 2, 0, libc::AF_INET as u32),
+        (BPF_JEQ_K, 1, 0, libc::AF_INET6 as u32),
+        (BPF_RET_K, 0, 0, SECCOMP_RET_ALLOW),
+        (BPF_RET_K, 0, 0, deny),
+    ];
+    let mut bytes = vec![];
+    for (code, jt, jf, k) in program {
+        bytes.extend(code.to_ne_bytes());
+        bytes.extend([jt, jf]);
+        bytes.extend(k.to_ne_bytes());
+    }
+    bytes
+}
+
+// An anonymous file with the filter, inherited only by the sandboxed process.
+fn filter_file(filter: &[u8]) -> Result<File> {
+    // SAFETY: the name is a valid C string, and the descriptor is owned by the file right after.
+    let fd = unsafe { libc::memfd_create(c"rover-seccomp".as_ptr(), libc::MFD_CLOEXEC) };
+    if fd < 0 {
+        return Err(std::io::Error::last_os_error()).context("failed to create seccomp filter");
+    }
+    // SAFETY: the descriptor is new and not owned by anything else.
+    let mut file = unsafe { File::from_raw_fd(fd) };
+    file.write_all(filter)?;
+    file.seek(SeekFrom::Start(0))?;
+    Ok(file)
+}
+
+fn find_program(name: &str) -> Option<PathBuf> {
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|dir| dir.join(name))
+        .find(|path| path.is_file())
+}
+
+// Dependencies are downloaded before the commands run, as cargo in the sandbox is offline and
+// can't write to the cargo home.
+fn fetch(workdir: &Path, toolchain: &Toolchain) -> Result<()> {
+    let mut cmd = toolchain.process(["cargo", "fetch"].into_iter())?;
+    // Wrappers from the cargo config of the solution would run outside the sandbox.
+    cmd.current_dir(workdir)
+        .env("RUSTC_WRAPPER", "")
+        .env("RUSTC_WORKSPACE_WRAPPER", "");
+    let output = cmd.output().context("failed to launch cargo fetch")?;
+    if !output.status.success() {
+        bail!(
+            "failed to fetch dependencies: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+// The target directory cargo builds into, which is the one of the root workspace for a member
+// problem. It's writable in the sandbox, so one containing the problem is refused.
+fn target_dir(output: &process::Output, workdir: &Path) -> Result<PathBuf> {
+    if !output.status.success() {
+        bail!(
+            "failed to read cargo metadata: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let metadata: serde_json::Value =
+        serde_json::from_slice(&output.stdout).context("invalid cargo metadata")?;
+    let (Some(target), Some(root)) = (
+        metadata["target_directory"].as_str().map(PathBuf::from),
+        metadata["workspace_root"].as_str(),
+    ) else {
+        bail!("cargo metadata has no target directory");
+    };
+    let workdir = fs::canonicalize(workdir).unwrap_or_else(|_| workdir.to_path_buf());
+    if !target.starts_with(root) || workdir.starts_with(&target) {
+        bail!("target directory {target:?} is not allowed in the sandbox");
+    }
+    Ok(target)
+}
+
+impl Sandbox {
+    // Fetches the dependencies and resolves the target directory once for the commands of a
+    // problem. The metadata is read in the sandbox without writable directories.
+    pub fn prepare(self, workdir: &Path, toolchain: &Toolchain) -> Result<PreparedSandbox> {
+        if !workdir.join(MANIFEST).is_file() {
+            let target_dir = workdir.join(TARGET_FOLDER);
+            return Ok(PreparedSandbox {
+                sandbox: self,
+                target_dir,
+            });
+        }
+        fetch(workdir, toolchain)?;
+        let metadata = toolchain
+            .process(["cargo", "metadata", "--format-version", "1", "--no-deps"].into_iter())?;
+        let output = self
+            .wrap(&metadata, workdir, &[])?
+            .output()
+            .context("failed to launch cargo metadata")?;
+        let target_dir = target_dir(&output, workdir)?;
+        Ok(PreparedSandbox {
+            sandbox: self,
+            target_dir,
+        })
+    }
+
+    // Runs the command in bubblewrap with a read-only view of the filesystem except the writable
+    // directories and a private /tmp, in its own PID namespace. Cargo runs offline.
+    fn wrap(
+        &self,
+        cmd: &process::Command,
+        workdir: &Path,
+        writable: &[PathBuf],
+    ) -> Result<process::Command> {
+        let Some(bwrap) = find_program(BWRAP) else {
+            bail!("sandbox requires bubblewrap, install it or remove \"sandbox\" from the config");
+        };
+        let current_dir = cmd.get_current_dir().unwrap_or(workdir).to_path_buf();
+        let mut sandboxed = process::Command::new(bwrap);
+        sandboxed.args(["--die-with-parent", "--unshare-pid"]);
+        if self.network != Network::Full {
+            sandboxed.arg("--unshare-net");
+        }
+        if self.network == Network::None {
+            let Some(arch) = AUDIT_ARCH else {
+                bail!("network \"none\" is not supported on this architecture, use \"loopback\"");
+            };
+            let file = filter_file(&deny_inet_filter(arch))?;
+            sandboxed.arg("--seccomp").arg(file.as_raw_fd().to_string());
+            // SAFETY: fcntl is async-signal-safe and only changes the flags of the descriptor
+            // in the child.
+            unsafe {
+                sandboxed.pre_exec(move || {
+                    if libc::fcntl(file.as_raw_fd(), libc::F_SETFD, 0) < 0 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                    Ok(())
+                });
+            }
+        }
+        sandboxed.args(["--ro-bind", "/", "/", "--dev", "/dev", "--proc", "/proc"]);
+        sandboxed.args(["--tmpfs", TMP_DIR, "--setenv", "TMPDIR", TMP_DIR]);
+        // The private /tmp hides a repository inside the real one, so it's mounted back.
+        if let Ok(inside) = workdir.strip_prefix(TMP_DIR) {
+            if let Some(top) = inside.iter().next() {
+                let top = Path::new(TMP_DIR).join(top);
+                sandboxed.arg("--ro-bind").arg(&top).arg(&top);
+            }
+        }
+        for dir in writable {
+            fs::create_dir_all(dir).with_context(|| format!("failed to create {dir:?}"))?;
+            sandboxed.arg("--bind").arg(dir).arg(dir);
+        }
+        sandboxed.arg("--chdir").arg(&current_dir).arg("--");
+        sandboxed.arg(cmd.get_program()).args(cmd.get_args());
+        for (key, value) in cmd.get_envs() {
+            match value {
+                Some(value) => sandboxed.env(key, value),
+                None => sandboxed.env_remove(key),
+            };
+        }
+        sandboxed.env("CARGO_NET_OFFLINE", "true");
+        sandboxed.current_dir(current_dir);
+        Ok(sandboxed)
+    }
+}
+
+pub struct PreparedSandbox {
+    sandbox: Sandbox,
+    target_dir: PathBuf,
+}
+
+impl PreparedSandbox {
+    // Only the target directory is writable.
+    pub fn wrap(&self, cmd: &process::Command, workdir: &Path) -> Result<process::Command> {
+        self.sandbox
+            .wrap(cmd, workdir, std::slice::from_ref(&self.target_dir))
+    }
+}
@@ -0,0 +1,216 @@
+use super::outcome::{Status, TestOutcome};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+const DOC_TESTS_PREFIX: &str = "Doc-tests ";
+const RUNNING_PREFIX: &str = "Running ";
+const UNITTESTS_PREFIX: &str = "unittests ";
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Scoring {
+    default_weight: f64,
+    tests: BTreeMap<String, f64>,
+    files: BTreeMap<String, f64>,
+}
+
+impl Default for Scoring {
+    fn default() -> Self {
+        Self {
+            default_weight: 1.0,
+            tests: BTreeMap::new(),
+            files: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct TestResult {
+    file: Option<String>,
+    name: String,
+    passed: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct Score {
+    passed: usize,
+    total: usize,
+    value: f64,
+}
+
+impl Score {
+    pub fn passed(&self) -> usize {
+        self.passed
+    }
+
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+}
+
+impl Scoring {
+    // Tests of a file or with a name that is not listed get the default weight. A test name
+    // matches its full path or any suffix of it, like "test_add" for "tests::test_add".
+    fn weight(&self, test: &TestResult) -> f64 {
+        self.tests
+            .iter()
+            .find(|(name, _)| test.name == **name || test.name.ends_with(&format!("::{name}")))
+            .map(|(_, weight)| *weight)
+            .or_else(|| {
+                test.file
+                    .as_ref()
+                    .and_then(|file| self.files.get(file))
+                    .copied()
+            })
+            .unwrap_or(self.default_weight)
+    }
+
+    // Returns None if no test results were found, so the outcome is scored as passed or failed.
+    // A failed command without test results, like a linter or a build failure, gives zero score,
+    // as does a command that exceeded a limit or was killed, whatever tests passed before.
+    pub fn score(&self, outcome: &TestOutcome) -> Option<Score> {
+        let mut tests: Vec<TestResult> = vec![];
+        let mut gate_failed = false;
+        for command in outcome.steps().iter().flat_map(|step| step.commands()) {
+            let results = parse_test_results(command.stdout(), command.stderr());
+            if command.status() == Status::Failed {
+                gate_failed |= results.is_empty()
+                    || command.failure_kind() != Some("failed")
+                    || command.exit_code().is_none();
+            }
+            for result in results {
+                match tests
+                    .iter_mut()
+                    .find(|test| test.file == result.file && test.name == result.name)
+                {
+                    Some(test) => test.passed &= result.passed,
+                    None => tests.push(result),
+                }
+            }
+        }
+        if tests.is_empty() {
+            return None;
+        }
+        let passed = tests.iter().filter(|test| test.passed).count();
+        let earned: f64 = tests
+            .iter()
+            .filter(|test| test.passed)
+            .map(|test| self.weight(test))
+            .sum();
+        let max: f64 = tests.iter().map(|test| self.weight(test)).sum();
+        let value = if gate_failed || max <= 0.0 {
+            0.0
+        } else {
+            earned / max
+        };
+        Some(Score {
+            passed,
+            total: tests.len(),
+            value,
+        })
+    }
+}
+
+// A binary that crashed reports fewer tests than it announced, the rest are failed. Results of
+// the binary start at `start`, and its ignored tests are announced but not scored.
+fn add_unreported(
+    results: &mut Vec<TestResult>,
+    file: &Option<String>,
+    announced: usize,
+    ignored: usize,
+    start: usize,
+) {
+    for index in results.len() - start..announced.saturating_sub(ignored) {
+        results.push(TestResult {
+            file: file.clone(),
+            name: format!("<unreported {}>", index + 1),
+            passed: false,
+        });
+    }
+}
+
+// Cargo prints the names of test binaries to stderr, and libtest prints the results to stdout
+// starting every binary with "running N tests". They are matched in order. Both the human and
+// the JSON output of libtest are understood.
+fn parse_test_results(stdout: &str, stderr: &str) -> Vec<TestResult> {
+    let mut files = stderr.lines().filter_map(|line| {
+        let line = line.trim();
+        if let Some(name) = line.strip_prefix(DOC_TESTS_PREFIX) {
+            Some(format!("doc-tests {name}"))
+        } else {
+            let target = line.strip_prefix(RUNNING_PREFIX)?;
+            let target = target.strip_prefix(UNITTESTS_PREFIX).unwrap_or(target);
+            Some(match target.split_once(" (") {
+                Some((file, _)) => file.to_string(),
+                None => target.to_string(),
+            })
+        }
+    });
+    let mut file = None;
+    let mut announced = 0;
+    let mut ignored = 0;
+    let mut start = 0;
+    let mut results = vec![];
+    for line in stdout.lines() {
+        let line = line.trim();
+        if line.starts_with('{') {
+            let Ok(event) = serde_json::from_str::<serde_json::Value>(line) else {
+                continue;
+            };
+            match (event["type"].as_str(), event["event"].as_str()) {
+                (Some("suite"), Some("started")) => {
+                    add_unreported(&mut results, &file, announced, ignored, start);
+                    file = files.next();
+                    announced = event["test_count"].as_u64().unwrap_or(0) as usize;
+                    ignored = 0;
+                    start = results.len();
+                }
+                (Some("test"), Some("ignored")) => ignored += 1,
+                (Some("test"), Some(result @ ("ok" | "failed" | "timeout"))) => {
+                    if let Some(name) = event["name"].as_str() {
+                        results.push(TestResult {
+                            file: file.clone(),
+                            name: name.to_string(),
+                            passed: result == "ok",
+                        });
+                    }
+                }
+                _ => {}
+            }
+        } else if let Some(count) = line
+            .strip_prefix("running ")
+            .and_then(|line| line.strip_suffix(" tests").or(line.strip_suffix(" test")))
+        {
+            add_unreported(&mut results, &file, announced, ignored, start);
+            file = files.next();
+            announced = count.parse().unwrap_or(0);
+            ignored = 0;
+            start = results.len();
+        } else if let Some(test) = line.strip_prefix("test ") {
+            let Some((name, result)) = test.rsplit_once(" ... ") else {
+                continue;
+            };
+            let passed = match result.trim() {
+                "ok" => true,
+                "FAILED" => false,
+                // Also "ignored, REASON".
+                result if result.starts_with("ignored") => {
+                    ignored += 1;
+                    continue;
+                }
+                _ => continue,
+            };
+            results.push(TestResult {
+                file: file.clone(),
+                name: name.trim_end_matches(" - should panic").to_string(),
+                passed,
+            });
+        }
+    }
+    add_unreported(&mut results, &file, announced, ignored, start);
+    results
+}
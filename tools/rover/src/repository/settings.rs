@@ -0,0 +1,150 @@
+use super::problem::Problem;
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+const CONFIG_VARIABLE: &str = "ROVER_CONFIG";
+const REPOSITORY_NAMES_VARIABLE: &str = "ROVER_REPOSITORY_NAMES";
+const ROOT_MARKERS_VARIABLE: &str = "ROVER_ROOT_MARKERS";
+const SOLUTIONS_REPO_VARIABLE: &str = "ROVER_SOLUTIONS_REPO";
+const REMOTE_VARIABLE: &str = "ROVER_REMOTE";
+const BASE_BRANCH_VARIABLE: &str = "ROVER_BASE_BRANCH";
+const BRANCH_PATTERN_VARIABLE: &str = "ROVER_BRANCH_PATTERN";
+
+// Base branches tried in order when none is set.
+const DEFAULT_BASE_BRANCHES: [&str; 2] = ["master", "main"];
+
+// User settings from `~/.config/rover/config.toml`, overridden by `ROVER_*` environment variables.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Settings {
+    repository_names: Vec<String>,
+    root_markers: Vec<String>,
+    solutions_repo: PathBuf,
+    remote: String,
+    base_branch: Option<String>,
+    branch_pattern: String,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            repository_names: vec!["mipt-rust".to_string(), "mipt-rust-private".to_string()],
+            root_markers: vec![],
+            solutions_repo: PathBuf::from("../solutions"),
+            remote: "origin".to_string(),
+            base_branch: None,
+            branch_pattern: "{group}/{title}".to_string(),
+        }
+    }
+}
+
+fn config_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os(CONFIG_VARIABLE) {
+        return Some(path.into());
+    }
+    let config_dir = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(config_dir.join("rover").join("config.toml"))
+}
+
+fn list_from_env(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+impl Settings {
+    pub fn load() -> Result<Self> {
+        let mut settings = match config_path() {
+            Some(path) if path.is_file() || env::var_os(CONFIG_VARIABLE).is_some() => {
+                let content = fs::read_to_string(&path)
+                    .with_context(|| format!("failed to read user config {path:?}"))?;
+                toml_edit::de::from_str(&content)
+                    .with_context(|| format!("invalid user config {path:?}"))?
+            }
+            _ => Self::default(),
+        };
+        if let Ok(names) = env::var(REPOSITORY_NAMES_VARIABLE) {
+            settings.repository_names = list_from_env(&names);
+        }
+        if let Ok(markers) = env::var(ROOT_MARKERS_VARIABLE) {
+            settings.root_markers = list_from_env(&markers);
+        }
+        if let Some(path) = env::var_os(SOLUTIONS_REPO_VARIABLE) {
+            settings.solutions_repo = path.into();
+        }
+        if let Ok(remote) = env::var(REMOTE_VARIABLE) {
+            settings.remote = remote;
+        }
+        if let Ok(branch) = env::var(BASE_BRANCH_VARIABLE) {
+            settings.base_branch = Some(branch).filter(|branch| !branch.is_empty());
+        }
+        if let Ok(pattern) = env::var(BRANCH_PATTERN_VARIABLE) {
+            settings.branch_pattern = pattern;
+        }
+        let rest = settings
+            .branch_pattern
+            .replace("{group}", "")
+            .replace("{title}", "");
+        if rest.contains(['{', '}']) || !settings.branch_pattern.contains("{title}") {
+            bail!(
+                "invalid branch pattern \"{}\": it must contain {{title}}, and {{group}} is the \
+                 only other placeholder",
+                settings.branch_pattern
+            );
+        }
+        Ok(settings)
+    }
+
+    // The course repository is the closest directory named as one of the repository names, or
+    // containing one of the root markers.
+    pub fn find_root(&self, path: &Path) -> Option<PathBuf> {
+        path.ancestors()
+            .find(|dir| {
+                dir.file_name().is_some_and(|name| {
+                    self.repository_names
+                        .iter()
+                        .any(|repository_name| *name == **repository_name)
+                }) || self
+                    .root_markers
+                    .iter()
+                    .any(|marker| dir.join(marker).exists())
+            })
+            .map(Path::to_path_buf)
+    }
+
+    // Relative to the root of the course repository.
+    pub fn solutions_repo(&self, root: &Path) -> PathBuf {
+        root.join(&self.solutions_repo)
+    }
+
+    pub fn remote(&self) -> &str {
+        &self.remote
+    }
+
+    // The remote branches to start a new problem branch from, in order.
+    pub fn start_points(&self) -> Vec<String> {
+        match &self.base_branch {
+            Some(branch) => vec![format!("{}/{branch}", self.remote)],
+            None => DEFAULT_BASE_BRANCHES
+                .iter()
+                .map(|branch| format!("{}/{branch}", self.remote))
+                .collect(),
+        }
+    }
+
+    pub fn branch_name(&self, problem: &Problem) -> String {
+        self.branch_pattern
+            .replace("{group}", &problem.group())
+            .replace("{title}", &problem.title())
+    }
+}
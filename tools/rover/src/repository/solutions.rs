@@ -0,0 +1,534 @@
+use crate::repository::settings::Settings;
+use anyhow::{Context, Result};
+use git2::{
+    build::CheckoutBuilder, Branch, BranchType, Commit, Config, Cred, CredentialType, ErrorClass,
+    ErrorCode, IndexAddOption, ObjectType, Oid, PushOptions, RemoteCallbacks, Repository,
+    Signature, Sort,
+};
+use std::{
+    cell::{Cell, RefCell},
+    env, fmt,
+    path::{Path, PathBuf},
+};
+
+// Credentials tried before giving up: the ssh agent and the default keys, or the credential
+// helper. libgit2 asks again after every rejected one.
+const MAX_CREDENTIAL_ATTEMPTS: usize = 3;
+const DEFAULT_SSH_KEYS: [&str; 2] = ["id_ed25519", "id_rsa"];
+
+#[derive(Debug)]
+pub enum GitError {
+    NothingToCommit,
+    BranchMissing(String),
+    NonFastForward { remote: String, branch: String },
+    AuthFailed { remote: String, message: String },
+    Git(git2::Error),
+}
+
+impl fmt::Display for GitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NothingToCommit => {
+                write!(f, "nothing to commit, no changes since the last submission")
+            }
+            Self::BranchMissing(branch) => write!(f, "branch {branch} does not exist"),
+            Self::NonFastForward { remote, branch } => write!(
+                f,
+                "{remote}/{branch} has commits missing from the local branch, pull them into the \
+                 solutions repository first"
+            ),
+            Self::AuthFailed { remote, message } => {
+                write!(f, "failed to authenticate to {remote}: {message}")
+            }
+            Self::Git(err) => write!(f, "{}", err.message()),
+        }
+    }
+}
+
+impl std::error::Error for GitError {}
+
+impl From<git2::Error> for GitError {
+    fn from(err: git2::Error) -> Self {
+        Self::Git(err)
+    }
+}
+
+fn credentials(
+    config: &Config,
+    url: &str,
+    username: Option<&str>,
+    allowed: CredentialType,
+    attempt: usize,
+) -> Result<Cred, git2::Error> {
+    let username = username.unwrap_or("git");
+    if allowed.contains(CredentialType::SSH_KEY) {
+        if attempt == 1 {
+            return Cred::ssh_key_from_agent(username);
+        }
+        let home = env::var_os("HOME").map(PathBuf::from).unwrap_or_default();
+        let key = DEFAULT_SSH_KEYS[(attempt - 2).min(DEFAULT_SSH_KEYS.len() - 1)];
+        return Cred::ssh_key(username, None, &home.join(".ssh").join(key), None);
+    }
+    if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) {
+        return Cred::credential_helper(config, url, Some(username));
+    }
+    if allowed.contains(CredentialType::USERNAME) {
+        return Cred::username(username);
+    }
+    Cred::default()
+}
+
+// Reads the submitted files from the branches of the solutions repository without checking them
+// out, and makes the submission commits.
+pub struct SolutionsRepo {
+    repo: Repository,
+}
+
+impl SolutionsRepo {
+    pub fn open(path: &Path) -> Result<Self> {
+        let repo = Repository::open(path)
+            .with_context(|| format!("failed to open solutions repository {path:?}"))?;
+        Ok(Self { repo })
+    }
+
+    // The local branch, or the remote one if it was submitted from another clone.
+    pub fn find_branch(&self, name: &str, settings: &Settings) -> Result<Option<Commit<'_>>> {
+        for (branch_name, branch_type) in [
+            (name.to_string(), BranchType::Local),
+            (format!("{}/{name}", settings.remote()), BranchType::Remote),
+        ] {
+            match self.repo.find_branch(&branch_name, branch_type) {
+                Ok(branch) => {
+                    let commit = branch
+                        .get()
+                        .peel_to_commit()
+                        .with_context(|| format!("branch {branch_name} has no commit"))?;
+                    return Ok(Some(commit));
+                }
+                Err(err) if err.code() == ErrorCode::NotFound => continue,
+                Err(err) => {
+                    return Err(err).with_context(|| format!("failed to find branch {branch_name}"))
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    // The content of the file at the path relative to the repository root, or None if it's not
+    // in the commit.
+    pub fn read_file(&self, commit: &Commit, path: &Path) -> Result<Option<Vec<u8>>> {
+        let tree = commit.tree().context("failed to read commit tree")?;
+        let entry = match tree.get_path(path) {
+            Ok(entry) => entry,
+            Err(err) if err.code() == ErrorCode::NotFound => return Ok(None),
+            Err(err) => return Err(err).with_context(|| format!("failed to find {path:?}")),
+        };
+        if entry.kind() != Some(ObjectType::Blob) {
+            return Ok(None);
+        }
+        let object = entry
+            .to_object(&self.repo)
+            .with_context(|| format!("failed to read {path:?}"))?;
+        Ok(object.as_blob().map(|blob| blob.content().to_vec()))
+    }
+
+    // The last commit changing the directory, following first parents from the commit.
+    pub fn last_change(&self, commit: &Commit, dir: &Path) -> Result<Option<Commit<'_>>> {
+        let entry_id = |commit: &Commit| commit.tree().ok()?.get_path(dir).ok().map(|e| e.id());
+        let mut revwalk = self.repo.revwalk()?;
+        revwalk.push(commit.id())?;
+        revwalk.simplify_first_parent()?;
+        revwalk.set_sorting(Sort::TOPOLOGICAL)?;
+        for oid in revwalk {
+            let commit = self.repo.find_commit(oid?)?;
+            let id = entry_id(&commit);
+            let parent_id = commit.parent(0).ok().and_then(|parent| entry_id(&parent));
+            if id.is_some() && id != parent_id {
+                return Ok(Some(commit));
+            }
+        }
+        Ok(None)
+    }
+
+    fn switch_to(&self, branch: &Branch) -> Result<(), GitError> {
+        let reference = branch.get();
+        let commit = reference.peel_to_commit()?;
+        self.repo
+            .checkout_tree(commit.as_object(), Some(CheckoutBuilder::new().safe()))?;
+        let name = reference
+            .name()
+            .ok_or_else(|| git2::Error::from_str("branch name is not valid utf-8"))?;
+        self.repo.set_head(name)?;
+        Ok(())
+    }
+
+    // Like `git checkout -b`, the branch is not kept if it can't be checked out.
+    fn switch_to_new(&self, mut branch: Branch) -> Result<(), GitError> {
+        let result = self.switch_to(&branch);
+        if result.is_err() {
+            branch.delete()?;
+        }
+        result
+    }
+
+    // Like `git checkout`: the local branch, or a new one tracking the remote branch.
+    pub fn checkout_branch(&self, name: &str, remote: &str) -> Result<(), GitError> {
+        match self.repo.find_branch(name, BranchType::Local) {
+            Ok(branch) => return self.switch_to(&branch),
+            Err(err) if err.code() != ErrorCode::NotFound => return Err(err.into()),
+            Err(_) => {}
+        }
+        let upstream = format!("{remote}/{name}");
+        match self.repo.find_branch(&upstream, BranchType::Remote) {
+            Ok(remote_branch) => {
+                let commit = remote_branch.get().peel_to_commit()?;
+                let mut branch = self.repo.branch(name, &commit, false)?;
+                branch.set_upstream(Some(&upstream))?;
+                self.switch_to_new(branch)
+            }
+            Err(err) if err.code() == ErrorCode::NotFound => {
+                Err(GitError::BranchMissing(name.to_string()))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    // Checks out the branch, or creates it from the first existing remote start point.
+    pub fn checkout_or_create(
+        &self,
+        name: &str,
+        remote: &str,
+        start_points: &[String],
+    ) -> Result<(), GitError> {
+        match self.checkout_branch(name, remote) {
+            Err(GitError::BranchMissing(_)) => {}
+            result => return result,
+        }
+        for start_point in start_points {
+            let start = match self.repo.find_branch(start_point, BranchType::Remote) {
+                Ok(start) => start,
+                Err(err) if err.code() == ErrorCode::NotFound => continue,
+                Err(err) => return Err(err.into()),
+            };
+            let commit = start.get().peel_to_commit()?;
+            let branch = self.repo.branch(name, &commit, false)?;
+            return self.switch_to_new(branch);
+        }
+        Err(GitError::BranchMissing(start_points.join(" or ")))
+    }
+
+    // Stages every change in the working tree, like `git add --all`.
+    pub fn add_all(&self) -> Result<(), GitError> {
+        let mut index = self.repo.index()?;
+        index.add_all(["*"], IndexAddOption::DEFAULT, None)?;
+        index.update_all(["*"], None)?;
+        index.write()?;
+        Ok(())
+    }
+
+    // From `GIT_AUTHOR_*` or `GIT_COMMITTER_*` variables, then `user.name` and `user.email`.
+    fn signature(&self, role: &str) -> Result<Signature<'static>, GitError> {
+        let config = self.repo.config()?;
+        let field = |variable: &str, key: &str| match env::var(format!("GIT_{role}_{variable}")) {
+            Ok(value) => Ok(value),
+            Err(_) => config.get_string(key).map_err(|err| match err.code() {
+                ErrorCode::NotFound => git2::Error::from_str(&format!(
+                    "{key} is not set, configure it with `git config --global {key} ...`"
+                )),
+                _ => err,
+            }),
+        };
+        Ok(Signature::now(
+            &field("NAME", "user.name")?,
+            &field("EMAIL", "user.email")?,
+        )?)
+    }
+
+    pub fn commit(&self, message: &str) -> Result<Oid, GitError> {
+        let mut index = self.repo.index()?;
+        let tree = self.repo.find_tree(index.write_tree()?)?;
+        let parent = match self.repo.head() {
+            Ok(head) => Some(head.peel_to_commit()?),
+            Err(err) if matches!(err.code(), ErrorCode::UnbornBranch | ErrorCode::NotFound) => None,
+            Err(err) => return Err(err.into()),
+        };
+        if parent
+            .as_ref()
+            .is_some_and(|parent| parent.tree_id() == tree.id())
+        {
+            return Err(GitError::NothingToCommit);
+        }
+        let author = self.signature("AUTHOR")?;
+        let committer = self.signature("COMMITTER")?;
+        let parents: Vec<&Commit> = parent.iter().collect();
+        Ok(self
+            .repo
+            .commit(Some("HEAD"), &author, &committer, message, &tree, &parents)?)
+    }
+
+    // Pushes the local branch to the branch of the same name and sets it as the upstream.
+    pub fn push(&self, remote_name: &str, branch: &str) -> Result<(), GitError> {
+        let mut remote = self.repo.find_remote(remote_name)?;
+        let config = self.repo.config()?;
+        let attempts = Cell::new(0);
+        let no_credentials = Cell::new(false);
+        let rejected = RefCell::new(None);
+        let mut callbacks = RemoteCallbacks::new();
+        callbacks.credentials(|url, username, allowed| {
+            attempts.set(attempts.get() + 1);
+            let result = if attempts.get() > MAX_CREDENTIAL_ATTEMPTS {
+                Err(git2::Error::from_str("no accepted credentials"))
+            } else {
+                credentials(&config, url, username, allowed, attempts.get())
+            };
+            no_credentials.set(result.is_err());
+            result
+        });
+        callbacks.push_update_reference(|_, status| {
+            if let Some(status) = status {
+                *rejected.borrow_mut() = Some(status.to_string());
+            }
+            Ok(())
+        });
+        let mut options = PushOptions::new();
+        options.remote_callbacks(callbacks);
+        let refspec = format!("refs/heads/{branch}:refs/heads/{branch}");
+        let non_fast_forward = || GitError::NonFastForward {
+            remote: remote_name.to_string(),
+            branch: branch.to_string(),
+        };
+        match remote.push(&[&refspec], Some(&mut options)) {
+            Ok(()) => {}
+            Err(err) if err.code() == ErrorCode::NotFastForward => return Err(non_fast_forward()),
+            Err(err)
+                if err.code() == ErrorCode::Auth
+                    || no_credentials.get()
+                    || (err.class() == ErrorClass::Ssh && attempts.get() > 0) =>
+            {
+                return Err(GitError::AuthFailed {
+                    remote: remote_name.to_string(),
+                    message: err.message().to_string(),
+                })
+            }
+            Err(err) => return Err(err.into()),
+        }
+        drop(options);
+        if let Some(status) = rejected.into_inner() {
+            if status.contains("fast-forward") || status.contains("fetch first") {
+                return Err(non_fast_forward());
+            }
+            return Err(git2::Error::from_str(&format!("push rejected: {status}")).into());
+        }
+        let mut local = self.repo.find_branch(branch, BranchType::Local)?;
+        local.set_upstream(Some(&format!("{remote_name}/{branch}")))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::RepositoryInitOptions;
+    use std::{
+        fs,
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        thread,
+    };
+    use tempfile::TempDir;
+
+    const BRANCH: &str = "main";
+    const REMOTE: &str = "origin";
+
+    fn init(path: &Path, bare: bool) -> Repository {
+        let mut options = RepositoryInitOptions::new();
+        options.bare(bare).initial_head(BRANCH);
+        let repo = Repository::init_opts(path, &options).unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Student").unwrap();
+        config.set_str("user.email", "student@example.com").unwrap();
+        repo
+    }
+
+    // A solutions repository with a bare repository as the remote.
+    fn setup(dir: &TempDir) -> SolutionsRepo {
+        let remote = dir.path().join("remote.git");
+        init(&remote, true);
+        let repo = init(&dir.path().join("local"), false);
+        repo.remote(REMOTE, remote.to_str().unwrap()).unwrap();
+        SolutionsRepo { repo }
+    }
+
+    fn clone(dir: &TempDir, name: &str) -> SolutionsRepo {
+        let remote = dir.path().join("remote.git");
+        let repo = Repository::clone(remote.to_str().unwrap(), dir.path().join(name)).unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Student").unwrap();
+        config.set_str("user.email", "student@example.com").unwrap();
+        SolutionsRepo { repo }
+    }
+
+    fn commit_file(solutions: &SolutionsRepo, name: &str, content: &str) -> Result<Oid, GitError> {
+        let workdir = solutions.repo.workdir().unwrap();
+        fs::write(workdir.join(name), content).unwrap();
+        solutions.add_all()?;
+        solutions.commit(name)
+    }
+
+    // Answers every request with 401, so every credential is rejected.
+    fn unauthorized_server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                while reader.read_line(&mut line).is_ok_and(|read| read > 2) {
+                    line.clear();
+                }
+                let _ = stream.write_all(
+                    b"HTTP/1.1 401 Unauthorized\r\nWWW-Authenticate: Basic realm=\"solutions\"\r\n\
+                      Content-Length: 0\r\nConnection: close\r\n\r\n",
+                );
+            }
+        });
+        format!("http://{address}/solutions.git")
+    }
+
+    #[test]
+    fn nothing_to_commit() {
+        let dir = TempDir::new().unwrap();
+        let solutions = setup(&dir);
+        commit_file(&solutions, "lib.rs", "fn main() {}").unwrap();
+        solutions.add_all().unwrap();
+        assert!(matches!(
+            solutions.commit("again"),
+            Err(GitError::NothingToCommit)
+        ));
+        commit_file(&solutions, "lib.rs", "fn main() { }").unwrap();
+    }
+
+    #[test]
+    fn branch_missing() {
+        let dir = TempDir::new().unwrap();
+        let solutions = setup(&dir);
+        commit_file(&solutions, "lib.rs", "").unwrap();
+        assert!(matches!(
+            solutions.checkout_branch("submit/demo-hello", REMOTE),
+            Err(GitError::BranchMissing(branch)) if branch == "submit/demo-hello"
+        ));
+        let start_points = ["origin/main".to_string(), "origin/master".to_string()];
+        assert!(matches!(
+            solutions.checkout_or_create("submit/demo-hello", REMOTE, &start_points),
+            Err(GitError::BranchMissing(branch)) if branch == "origin/main or origin/master"
+        ));
+        assert!(solutions
+            .repo
+            .find_branch("submit/demo-hello", BranchType::Local)
+            .is_err());
+    }
+
+    #[test]
+    fn checkout_remote_branch() {
+        let dir = TempDir::new().unwrap();
+        let solutions = setup(&dir);
+        commit_file(&solutions, "lib.rs", "").unwrap();
+        solutions.push(REMOTE, BRANCH).unwrap();
+        let other = clone(&dir, "other");
+        other
+            .checkout_or_create("submit/demo-hello", REMOTE, &["origin/main".to_string()])
+            .unwrap();
+        commit_file(&other, "hello.rs", "").unwrap();
+        other.push(REMOTE, "submit/demo-hello").unwrap();
+
+        solutions
+            .repo
+            .find_remote(REMOTE)
+            .unwrap()
+            .fetch(&[] as &[&str], None, None)
+            .unwrap();
+        solutions
+            .checkout_branch("submit/demo-hello", REMOTE)
+            .unwrap();
+        let head = solutions.repo.head().unwrap();
+        assert_eq!(head.shorthand(), Some("submit/demo-hello"));
+        let upstream = solutions
+            .repo
+            .find_branch("submit/demo-hello", BranchType::Local)
+            .unwrap();
+        assert!(upstream.upstream().is_ok());
+    }
+
+    #[test]
+    fn non_fast_forward() {
+        let dir = TempDir::new().unwrap();
+        let solutions = setup(&dir);
+        commit_file(&solutions, "lib.rs", "").unwrap();
+        solutions.push(REMOTE, BRANCH).unwrap();
+        let other = clone(&dir, "other");
+        commit_file(&other, "other.rs", "").unwrap();
+        other.push(REMOTE, BRANCH).unwrap();
+
+        commit_file(&solutions, "local.rs", "").unwrap();
+        assert!(matches!(
+            solutions.push(REMOTE, BRANCH),
+            Err(GitError::NonFastForward { remote, branch }) if remote == REMOTE && branch == BRANCH
+        ));
+    }
+
+    #[test]
+    fn rejected_credentials() {
+        let dir = TempDir::new().unwrap();
+        let solutions = setup(&dir);
+        solutions
+            .repo
+            .remote_set_url(REMOTE, &unauthorized_server())
+            .unwrap();
+        let mut config = solutions.repo.config().unwrap();
+        config
+            .set_str(
+                "credential.helper",
+                "!f() { echo username=student; echo password=wrong; }; f",
+            )
+            .unwrap();
+        commit_file(&solutions, "lib.rs", "").unwrap();
+        assert!(matches!(
+            solutions.push(REMOTE, BRANCH),
+            Err(GitError::AuthFailed { remote, .. }) if remote == REMOTE
+        ));
+    }
+
+    #[test]
+    fn missing_credentials() {
+        let dir = TempDir::new().unwrap();
+        let solutions = setup(&dir);
+        solutions
+            .repo
+            .remote_set_url(REMOTE, &unauthorized_server())
+            .unwrap();
+        let mut config = solutions.repo.config().unwrap();
+        config.set_str("credential.helper", "").unwrap();
+        commit_file(&solutions, "lib.rs", "").unwrap();
+        assert!(matches!(
+            solutions.push(REMOTE, BRANCH),
+            Err(GitError::AuthFailed { remote, .. }) if remote == REMOTE
+        ));
+    }
+
+    #[test]
+    fn connection_failure_is_not_auth() {
+        let dir = TempDir::new().unwrap();
+        let solutions = setup(&dir);
+        let closed = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let url = format!("ssh://git@{closed}/solutions.git");
+        solutions.repo.remote_set_url(REMOTE, &url).unwrap();
+        commit_file(&solutions, "lib.rs", "").unwrap();
+        assert!(matches!(
+            solutions.push(REMOTE, BRANCH),
+            Err(GitError::Git(_))
+        ));
+    }
+}
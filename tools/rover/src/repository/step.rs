@@ -1,23 +1,41 @@
-use super::command::Command;
+use super::{command::Command, limits::Limits};
+
+#[derive(Debug)]
+pub struct StepCommand {
+    command: Command,
+    limits: Limits,
+}
+
+impl StepCommand {
+    pub fn new(command: Command, limits: Limits) -> Self {
+        Self { command, limits }
+    }
+
+    pub fn command(&self) -> &Command {
+        &self.command
+    }
+
+    pub fn limits(&self) -> &Limits {
+        &self.limits
+    }
+}
 
 #[derive(Debug)]
 pub struct Step {
-    #[allow(unused)]
     name: String,
-    commands: Vec<Command>,
+    commands: Vec<StepCommand>,
 }
 
 impl Step {
-    pub fn new(name: String, commands: Vec<Command>) -> Self {
+    pub fn new(name: String, commands: Vec<StepCommand>) -> Self {
         Self { name, commands }
     }
 
-    #[allow(unused)]
     pub fn name(&self) -> &str {
         &self.name
     }
 
-    pub fn commands(&self) -> &[Command] {
+    pub fn commands(&self) -> &[StepCommand] {
         self.commands.as_slice()
     }
 }
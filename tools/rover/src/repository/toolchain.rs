@@ -1,216 +1,448 @@
-use super::{command::Command, context::CommandContext};
+use super::{
+    bench::check_results,
+    command::{Command, Expectation},
+    compile_fail::{check_snapshot, normalize_errors},
+    context::CommandContext,
+    execution::{execute, Execution},
+    forbid::{check_forbid_collections, check_forbid_std, check_forbid_unsafe, Violation},
+    limits::Limits,
+    outcome::CommandOutcome,
+};
 use anyhow::{bail, Context, Result};
+use chrono::NaiveDate;
 use std::{
-    fs::File,
-    io::{BufRead, BufReader},
+    io::{self, Write},
+    path::Path,
     process,
+    time::Instant,
 };
 
-const FORBID_UNSAFE_LINE: &str = "#![forbid(unsafe_code)]";
-const FORBID_STD_LINE: &str = "#![no_std]";
-const FORBID_COLLECTIONS_PATTERNS: [&str; 8] = [
-    "BTreeMap",
-    "BTreeSet",
-    "HashMap",
-    "HashSet",
-    "Vec",
-    "VecDeque",
-    "LinkedList",
-    "BinaryHeap",
-];
-
-macro_rules! launch {
-    ($toolchain: expr, $command: expr, $context: expr) => {{
-        let toolchain_shell_line = $toolchain.get_shell_line()?;
-        let command_shell_line = $command.get_shell_line()?;
-        let toolchain_shell_iter = toolchain_shell_line.split(' ');
-        let command_shell_iter = command_shell_line.split(' ');
-        let mut iter = toolchain_shell_iter.chain(command_shell_iter);
-        let mut cmd = if let Some(program) = iter.next() {
-            let mut cmd = process::Command::new(program);
-            cmd.current_dir($context.get_workdir());
-            cmd
-        } else {
-            bail!("toolchain and command are empty")
-        };
-        while let Some(arg) = iter.next() {
-            cmd.arg(arg);
-        }
-        if cmd.status().context("command failed")?.success() {
-            Ok(())
-        } else {
-            bail!("command failed")
-        }
-    }};
+// Runs the command in the workdir and the sandbox, returning its shell line and execution.
+fn run(
+    cmd: &mut process::Command,
+    limits: &Limits,
+    context: &CommandContext,
+) -> Result<(String, Execution)> {
+    let shell_line = std::iter::once(cmd.get_program())
+        .chain(cmd.get_args())
+        .map(|part| part.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join(" ");
+    if cmd.get_current_dir().is_none() {
+        cmd.current_dir(context.get_workdir());
+    }
+    if let Some(sandbox) = context.get_sandbox() {
+        *cmd = sandbox.wrap(cmd, context.get_workdir())?;
+    }
+    let execution = execute(cmd, limits, context.get_cancel())
+        .with_context(|| format!("failed to launch \"{shell_line}\""))?;
+    Ok((shell_line, execution))
+}
+
+fn launch(
+    cmd: &mut process::Command,
+    limits: &Limits,
+    context: &CommandContext,
+    outcome: &mut CommandOutcome,
+) -> Result<Execution> {
+    let (shell_line, execution) = run(cmd, limits, context)?;
+    if context.echo_output() {
+        io::stdout().lock().write_all(&execution.stdout)?;
+        io::stderr().lock().write_all(&execution.stderr)?;
+    }
+    outcome.record(shell_line, &execution);
+    if let Some(exceeded) = execution.exceeded {
+        return Err(exceeded.into());
+    }
+    Ok(execution)
+}
+
+const EMPTY: &str = "empty";
+const NAMED_CHANNELS: [&str; 3] = ["stable", "beta", "nightly"];
+
+// A rustup toolchain with the components and targets a problem needs, or the empty one that
+// launches programs from PATH as they are.
+#[derive(Clone, Debug, Default)]
+pub struct Toolchain {
+    channel: Option<String>,
+    components: Vec<String>,
+    targets: Vec<String>,
+}
+
+// Named channels optionally dated like nightly-2023-05-01, or versions like 1.70 and 1.70.0.
+fn is_valid_channel(name: &str) -> bool {
+    let (channel, date) = match name.split_once('-') {
+        Some((channel, date)) => (channel, Some(date)),
+        None => (name, None),
+    };
+    if NAMED_CHANNELS.contains(&channel) {
+        return date.is_none_or(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok());
+    }
+    let parts: Vec<_> = channel.split('.').collect();
+    date.is_none()
+        && (2..=3).contains(&parts.len())
+        && parts
+            .iter()
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
 }
 
-#[derive(Clone, Copy, Debug)]
-pub enum Toolchain {
-    Empty,
-    Stable,
-    Nightly,
+// The components without which the command fails.
+fn required_components(command: &Command) -> &'static [&'static str] {
+    match command {
+        Command::CargoMiriTest => &["miri", "rust-src"],
+        Command::CargoClippy => &["clippy"],
+        Command::CargoFmt => &["rustfmt"],
+        _ => &[],
+    }
+}
+
+fn rustup_lines(args: &[&str]) -> Result<Vec<String>> {
+    let output = process::Command::new("rustup")
+        .args(args)
+        .output()
+        .context("failed to launch rustup, it's required for the toolchain of the problem")?;
+    if !output.status.success() {
+        bail!(
+            "rustup {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::to_string)
+        .collect())
 }
 
 impl Toolchain {
     pub fn from_name(name: &str) -> Result<Self> {
-        Ok(match name {
-            "empty" => Self::Empty,
-            "stable" => Self::Stable,
-            "nightly" => Self::Nightly,
-            name => bail!("toolchain \"{name}\" is not supported"),
+        if name == EMPTY {
+            return Ok(Self::default());
+        }
+        if !is_valid_channel(name) {
+            bail!(
+                "toolchain \"{name}\" is not supported, expected \"empty\", a channel like \"stable\" \
+                 or \"nightly-2023-05-01\", or a version like \"1.70.0\""
+            );
+        }
+        Ok(Self {
+            channel: Some(name.to_string()),
+            ..Self::default()
         })
     }
 
+    pub fn add_component(&mut self, name: &str) -> Result<()> {
+        if self.channel.is_none() {
+            bail!("the empty toolchain has no components");
+        }
+        if !is_valid_name(name) {
+            bail!("invalid component name \"{name}\"");
+        }
+        self.components.push(name.to_string());
+        Ok(())
+    }
+
+    pub fn add_target(&mut self, name: &str) -> Result<()> {
+        if self.channel.is_none() {
+            bail!("the empty toolchain has no targets");
+        }
+        if !is_valid_name(name) {
+            bail!("invalid target name \"{name}\"");
+        }
+        self.targets.push(name.to_string());
+        Ok(())
+    }
+
+    pub fn name(&self) -> &str {
+        self.channel.as_deref().unwrap_or(EMPTY)
+    }
+
     pub fn get_shell_line(&self) -> Result<String> {
-        Ok(match self {
-            Self::Empty => "".to_string(),
-            Self::Stable => "rustup run stable".to_string(),
-            Self::Nightly => "rustup run nightly".to_string(),
+        Ok(match &self.channel {
+            None => "".to_string(),
+            Some(channel) => format!("rustup run {channel}"),
         })
     }
 
-    pub fn run_command(&self, command: &Command, context: &CommandContext) -> Result<()> {
-        match command {
-            Command::ForbidUnsafe => {
-                for file in context.get_user_files() {
-                    if let Some(line) = BufReader::new(File::open(file)?)
-                        .lines()
-                        .next()
-                        .transpose()?
-                    {
-                        if line != FORBID_UNSAFE_LINE {
-                            bail!(format!(
-                                "file {file:?} does not contain line '{FORBID_UNSAFE_LINE}'"
-                            ))
-                        }
-                    } else {
-                        // TODO: ForbidUnsafe shouldn't check whether file is empty
-                        bail!(format!("file {file:?} is empty"))
-                    }
+    // Fails with the rustup commands to install what's missing: the toolchain, its components
+    // from the config and the ones the commands need, and its targets.
+    pub fn check_installed<'a>(
+        &self,
+        commands: impl IntoIterator<Item = &'a Command>,
+    ) -> Result<()> {
+        let Some(channel) = &self.channel else {
+            return Ok(());
+        };
+        let mut components: Vec<&str> = self.components.iter().map(String::as_str).collect();
+        for command in commands {
+            for component in required_components(command) {
+                if !components.contains(component) {
+                    components.push(component);
                 }
-                Ok(())
             }
-            Command::ForbidCollections => {
-                for file in context.get_user_files() {
-                    for line in BufReader::new(File::open(file)?).lines() {
-                        let line = line.context("error reading file line")?;
-                        for pattern in FORBID_COLLECTIONS_PATTERNS {
-                            if line.contains(pattern) {
-                                bail!(format!("file {file:?} contains line '{pattern}'"))
-                            }
-                        }
+        }
+
+        // Toolchains are listed with the host, like "nightly-x86_64-unknown-linux-gnu (default)".
+        let toolchain_installed = rustup_lines(&["toolchain", "list"])?.iter().any(|line| {
+            let name = line.split(' ').next().unwrap_or_default();
+            name == channel
+                || name
+                    .strip_prefix(channel.as_str())
+                    .and_then(|rest| rest.strip_prefix('-'))
+                    .is_some_and(|host| host.starts_with(|c: char| c.is_ascii_alphabetic()))
+        });
+        if !toolchain_installed {
+            let mut install = format!("rustup toolchain install {channel}");
+            if !components.is_empty() {
+                install += &format!(" --component {}", components.join(","));
+            }
+            if !self.targets.is_empty() {
+                install += &format!(" --target {}", self.targets.join(","));
+            }
+            bail!("toolchain {channel} is not installed, run:\n  {install}");
+        }
+
+        let installed_targets =
+            rustup_lines(&["target", "list", "--installed", "--toolchain", channel])?;
+        let installed_components =
+            rustup_lines(&["component", "list", "--installed", "--toolchain", channel])?;
+        // Components are listed with their target, like "clippy-x86_64-unknown-linux-gnu", or
+        // without one, like "rust-src".
+        let missing_components: Vec<&str> = components
+            .into_iter()
+            .filter(|component| {
+                !installed_components.iter().any(|name| {
+                    name == component
+                        || installed_targets
+                            .iter()
+                            .any(|target| *name == format!("{component}-{target}"))
+                })
+            })
+            .collect();
+        let missing_targets: Vec<&str> = self
+            .targets
+            .iter()
+            .filter(|target| !installed_targets.contains(target))
+            .map(String::as_str)
+            .collect();
+        let mut fixes = vec![];
+        if !missing_components.is_empty() {
+            fixes.push(format!(
+                "rustup component add {} --toolchain {channel}",
+                missing_components.join(" ")
+            ));
+        }
+        if !missing_targets.is_empty() {
+            fixes.push(format!(
+                "rustup target add {} --toolchain {channel}",
+                missing_targets.join(" ")
+            ));
+        }
+        if !fixes.is_empty() {
+            bail!(
+                "toolchain {channel} misses components or targets, run:\n  {}",
+                fixes.join("\n  ")
+            );
+        }
+        Ok(())
+    }
+
+    pub fn run_command(
+        &self,
+        command: &Command,
+        limits: &Limits,
+        context: &CommandContext,
+    ) -> CommandOutcome {
+        let mut outcome = CommandOutcome::new(command.name(), self.name());
+        let start = Instant::now();
+        let result = self.launch_command(command, limits, context, &mut outcome);
+        outcome.finish(start.elapsed(), result);
+        outcome
+    }
+
+    fn launch_command(
+        &self,
+        command: &Command,
+        limits: &Limits,
+        context: &CommandContext,
+        outcome: &mut CommandOutcome,
+    ) -> Result<()> {
+        match command {
+            Command::ForbidUnsafe => check_user_files(context, check_forbid_unsafe),
+            Command::ForbidCollections => check_user_files(context, check_forbid_collections),
+            Command::ForbidStd => check_user_files(context, check_forbid_std),
+            Command::CargoFmt
+            | Command::CargoClippy
+            | Command::CargoTest
+            | Command::CargoTestDebug
+            | Command::PythonTest
+            | Command::CargoMiriTest => {
+                let command_shell_line = command.get_shell_line()?;
+                let mut cmd = self.process(command_shell_line.split(' '))?;
+                if let Some(filter) = context.test_filter() {
+                    if matches!(
+                        command,
+                        Command::CargoTest | Command::CargoTestDebug | Command::CargoMiriTest
+                    ) {
+                        cmd.arg(filter);
                     }
                 }
-                Ok(())
+                if launch(&mut cmd, limits, context, outcome)?.status.success() {
+                    Ok(())
+                } else {
+                    bail!(exit_failure(outcome))
+                }
             }
-            Command::ForbidStd => {
-                for file in context.get_user_files() {
-                    if let Some(line) = BufReader::new(File::open(file)?)
-                        .lines()
-                        .next()
-                        .transpose()?
-                    {
-                        if line != FORBID_STD_LINE {
-                            bail!(format!(
-                                "file {file:?} does not contain line '{FORBID_STD_LINE}'"
-                            ))
-                        }
+            Command::CompileFail(cases) => {
+                let mut mismatches = vec![];
+                for case in cases {
+                    if context.is_cancelled() {
+                        break;
+                    }
+                    let command_shell_line = command.get_shell_line()?;
+                    let mut cmd = self.process(command_shell_line.split(' '))?;
+                    cmd.arg(case);
+                    let (shell_line, mut execution) = run(&mut cmd, limits, context)?;
+                    let (errors, rendered) =
+                        normalize_errors(&execution.stdout, context.get_workdir());
+                    // The rendered errors are kept instead of the JSON messages.
+                    execution.stdout = rendered.concat().into_bytes();
+                    if context.echo_output() {
+                        io::stderr().lock().write_all(&execution.stdout)?;
+                    }
+                    outcome.record(shell_line, &execution);
+                    if let Some(exceeded) = execution.exceeded {
+                        return Err(exceeded.into());
+                    }
+                    let mismatch = if execution.status.success() {
+                        Some("compiled, but it was expected to fail".to_string())
+                    } else if errors.is_empty() {
+                        Some(format!(
+                            "failed without compiler errors:\n{}",
+                            String::from_utf8_lossy(&execution.stderr).trim_end()
+                        ))
                     } else {
-                        // TODO: ForbidStd shouldn't check whether file is empty
-                        bail!(format!("file {file:?} is empty"))
+                        check_snapshot(context.get_workdir(), case, &errors)?
+                    };
+                    if let Some(mismatch) = mismatch {
+                        mismatches.push(format!("case \"{case}\" {mismatch}"));
                     }
                 }
-                Ok(())
-            }
-            Command::CargoCompileTestMiniFrunk => {
-                // TODO: hardcoded, better to refactor all the code
-                if process::Command::new("cargo")
-                    .current_dir(context.get_workdir())
-                    .arg("test")
-                    .arg("--features")
-                    .arg("compilation-fail-generic")
-                    .status()
-                    .context("command failed")?
-                    .success()
-                {
-                    bail!("command failed")
-                }
-                if process::Command::new("cargo")
-                    .current_dir(context.get_workdir())
-                    .arg("test")
-                    .arg("--features")
-                    .arg("compilation-fail-labelled")
-                    .status()
-                    .context("command failed")?
-                    .success()
-                {
-                    bail!("command failed")
-                }
-                if process::Command::new("cargo")
-                    .current_dir(context.get_workdir())
-                    .arg("test")
-                    .arg("--features")
-                    .arg("compilation-fail-transmogrify")
-                    .status()
-                    .context("command failed")?
-                    .success()
-                {
-                    bail!("command failed")
+                if mismatches.is_empty() {
+                    return Ok(());
                 }
-                println!("Compile tests passed, don't worry :)");
-                Ok(())
+                bail!(
+                    "{} of {} case(s) mismatched:\n{}",
+                    mismatches.len(),
+                    cases.len(),
+                    mismatches.join("\n")
+                )
             }
-            Command::CargoCompileTestOrm => {
-                // TODO: hardcoded, better to refactor all the code
-                if process::Command::new("cargo")
-                    .current_dir(context.get_workdir())
-                    .arg("test")
-                    .arg("--features")
-                    .arg("test-lifetimes-create")
-                    .status()
-                    .context("command failed")?
-                    .success()
-                {
-                    bail!("command failed")
+            Command::CargoBench(thresholds) => {
+                let mut cmd = self.process(command.get_shell_line()?.split(' '))?;
+                let execution = launch(&mut cmd, limits, context, outcome)?;
+                if !execution.status.success() {
+                    bail!(exit_failure(outcome))
                 }
-                if process::Command::new("cargo")
-                    .current_dir(context.get_workdir())
-                    .arg("test")
-                    .arg("--features")
-                    .arg("test-lifetimes-get")
-                    .status()
-                    .context("command failed")?
-                    .success()
-                {
-                    bail!("command failed")
-                }
-                println!("Compile tests passed, don't worry :)");
-                Ok(())
+                check_results(
+                    context.get_workdir(),
+                    thresholds,
+                    &String::from_utf8_lossy(&execution.stdout),
+                )
             }
-            Command::CargoCompileTestSnapshot => {
-                // TODO: hardcoded, better to refactor all the code
-                if process::Command::new("cargo")
-                    .current_dir(context.get_workdir())
-                    .arg("test")
-                    .arg("--features")
-                    .arg("test-lifetime")
-                    .status()
-                    .context("command failed")?
-                    .success()
-                {
-                    bail!("command failed")
+            Command::Custom(custom) => {
+                let mut runs = custom
+                    .features()
+                    .iter()
+                    .map(|feature| vec!["--features", feature.as_str()])
+                    .collect::<Vec<_>>();
+                if runs.is_empty() {
+                    runs.push(vec![]);
+                }
+                // The features go before `--`, so they aren't passed to the test binary.
+                let args = custom.args().iter().map(String::as_str).collect::<Vec<_>>();
+                let split = args
+                    .iter()
+                    .position(|arg| *arg == "--")
+                    .unwrap_or(args.len());
+                for extra_args in runs {
+                    let line = std::iter::once(custom.program())
+                        .chain(args[..split].iter().copied())
+                        .chain(extra_args)
+                        .chain(args[split..].iter().copied())
+                        .collect::<Vec<_>>();
+                    let mut cmd = self.process(line.iter().copied())?;
+                    cmd.envs(custom.env());
+                    if let Some(workdir) = custom.workdir() {
+                        cmd.current_dir(context.get_workdir().join(workdir));
+                    }
+                    let success = launch(&mut cmd, limits, context, outcome)?.status.success();
+                    match (custom.expect(), success) {
+                        (Expectation::Success, false) => bail!(exit_failure(outcome)),
+                        (Expectation::Failure, true) => {
+                            bail!(
+                                "command \"{}\" succeeded, but it was expected to fail",
+                                line.join(" ")
+                            )
+                        }
+                        _ => {}
+                    }
                 }
-                println!("Compile tests passed, don't worry :)");
                 Ok(())
             }
-            Command::CargoFmt
-            | Command::CargoClippy
-            | Command::CargoTest
-            | Command::CargoTestDebug
-            | Command::PythonTest
-            | Command::CargoMiriTest => {
-                launch!(self, command, context)
-            }
         }
     }
+
+    pub fn process<'a>(&self, command: impl Iterator<Item = &'a str>) -> Result<process::Command> {
+        let toolchain_shell_line = self.get_shell_line()?;
+        let mut iter = toolchain_shell_line
+            .split(' ')
+            .filter(|part| !part.is_empty())
+            .map(str::to_string)
+            .chain(command.map(str::to_string));
+        let mut cmd = match iter.next() {
+            Some(program) => process::Command::new(program),
+            None => bail!("toolchain and command are empty"),
+        };
+        cmd.args(iter);
+        Ok(cmd)
+    }
+}
+
+fn check_user_files(
+    context: &CommandContext,
+    check: fn(&Path) -> Result<Vec<Violation>>,
+) -> Result<()> {
+    let mut violations = vec![];
+    for file in context.get_user_files() {
+        let relative = file.strip_prefix(context.get_workdir()).unwrap_or(file);
+        violations.extend(
+            check(file)?
+                .into_iter()
+                .map(|violation| violation.with_file(relative)),
+        );
+    }
+    if violations.is_empty() {
+        return Ok(());
+    }
+    let report = violations
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("\n");
+    bail!("found {} violation(s):\n{report}", violations.len())
+}
+
+fn exit_failure(outcome: &CommandOutcome) -> String {
+    match outcome.exit_code() {
+        Some(code) => format!("command exited with code {code}"),
+        None => "command was terminated by a signal".to_string(),
+    }
 }
@@ -0,0 +1,50 @@
+use super::{
+    register::{register_in_compose_config, register_in_workspace},
+    template::Template,
+};
+use crate::repository::repo::{Repository, COMPOSE_CONFIG, PROBLEMS_FOLDER};
+use anyhow::{bail, Context, Result};
+use std::{fs, path::Path};
+
+const CARGO_ROOT: &str = "Cargo.toml";
+
+pub fn init_problem(path: &Path, group: &str, title: &str, template: &str) -> Result<()> {
+    let template = Template::from_name(template)?;
+    for name in [group, title] {
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        {
+            bail!("\"{name}\" must consist of lowercase letters, digits and '-'")
+        }
+    }
+    let repository = Repository::from_path(path)?;
+    let root = repository.get_path();
+    let relative_path = Path::new(PROBLEMS_FOLDER).join(group).join(title);
+    let problem_path = root.join(&relative_path);
+    if problem_path.exists() {
+        bail!("problem {problem_path:?} already exists")
+    }
+    for (file, content) in template.files(title) {
+        let file = problem_path.join(file);
+        fs::create_dir_all(file.parent().context("file has no parent")?)
+            .with_context(|| format!("failed to create directory for {file:?}"))?;
+        fs::write(&file, content).with_context(|| format!("failed to write {file:?}"))?;
+    }
+    println!("Created {}", relative_path.display());
+    let cargo_root = root.join(CARGO_ROOT);
+    if cargo_root.is_file() {
+        register_in_workspace(
+            &cargo_root,
+            relative_path.to_str().context("non-utf-8 path")?,
+        )?;
+        println!("Added {} to {CARGO_ROOT}", relative_path.display());
+    }
+    let compose_config = root.join(COMPOSE_CONFIG);
+    if compose_config.is_file() {
+        register_in_compose_config(&compose_config, &format!("{group}/{title}"))?;
+        println!("Added {group}/{title} to {COMPOSE_CONFIG}");
+    }
+    Ok(())
+}
@@ -0,0 +1,3 @@
+pub mod init;
+mod register;
+mod template;
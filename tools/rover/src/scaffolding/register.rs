@@ -0,0 +1,74 @@
+use crate::repository::repo::PROBLEMS_FOLDER;
+use anyhow::{bail, Context, Result};
+use std::{fs, path::Path};
+use toml_edit::{DocumentMut, Value};
+
+const PROBLEMS_KEY: &str = "problems:";
+
+// Inserts the problem after the last problem in the workspace members, so comments and the
+// grouping of members stay as they are.
+pub fn register_in_workspace(cargo_root: &Path, member: &str) -> Result<()> {
+    let content =
+        fs::read_to_string(cargo_root).with_context(|| format!("failed to read {cargo_root:?}"))?;
+    let mut document: DocumentMut = content
+        .parse()
+        .with_context(|| format!("failed to parse {cargo_root:?}"))?;
+    let members = document
+        .get_mut("workspace")
+        .and_then(|workspace| workspace.get_mut("members"))
+        .and_then(|members| members.as_array_mut())
+        .context("no workspace members in the root Cargo.toml")?;
+    if members.iter().any(|value| value.as_str() == Some(member)) {
+        return Ok(());
+    }
+    let problems_prefix = format!("{PROBLEMS_FOLDER}/");
+    let position = members
+        .iter()
+        .enumerate()
+        .filter(|(_, value)| {
+            value
+                .as_str()
+                .is_some_and(|path| path.starts_with(&problems_prefix))
+        })
+        .map(|(position, _)| position + 1)
+        .last()
+        .unwrap_or(members.len());
+    members.insert_formatted(position, Value::from(member).decorated("\n    ", ""));
+    if position == members.len() - 1 {
+        members.set_trailing_comma(true);
+        members.set_trailing("\n");
+    }
+    fs::write(cargo_root, document.to_string())
+        .with_context(|| format!("failed to write {cargo_root:?}"))
+}
+
+// The config is edited as text to keep its comments and formatting.
+pub fn register_in_compose_config(compose_config: &Path, problem: &str) -> Result<()> {
+    let content = fs::read_to_string(compose_config)
+        .with_context(|| format!("failed to read {compose_config:?}"))?;
+    let mut lines: Vec<&str> = content.lines().collect();
+    let start = lines
+        .iter()
+        .position(|line| line.trim_end() == PROBLEMS_KEY)
+        .with_context(|| format!("no problems list in {compose_config:?}"))?;
+    let mut last_item = None;
+    for (index, line) in lines.iter().enumerate().skip(start + 1) {
+        let trimmed = line.trim();
+        if let Some(item) = trimmed.strip_prefix("- ") {
+            if item.trim() == problem {
+                return Ok(());
+            }
+            last_item = Some(index);
+        } else if !trimmed.is_empty() && !trimmed.starts_with('#') {
+            break;
+        }
+    }
+    let Some(last_item) = last_item else {
+        bail!("problems list in {compose_config:?} is empty or not a block list")
+    };
+    let indent = &lines[last_item][..lines[last_item].len() - lines[last_item].trim_start().len()];
+    let item = format!("{indent}- {problem}");
+    lines.insert(last_item + 1, &item);
+    fs::write(compose_config, lines.join("\n") + "\n")
+        .with_context(|| format!("failed to write {compose_config:?}"))
+}
@@ -0,0 +1,181 @@
+use anyhow::{bail, Result};
+use std::path::PathBuf;
+
+const CONFIG: &str = r#"toolchain: stable
+allowed-patterns:
+  - $SOURCE
+steps:
+  linters:
+    - forbid-unsafe
+    - cargo-fmt
+    - cargo-clippy
+  testing:
+    - cargo-test
+"#;
+
+const README: &str = r#"# $TITLE
+
+## Task
+
+Describe the problem here. Tests are located in the `tests/tests.rs` file.
+"#;
+
+const PACKAGE: &str = r#"[package]
+name = "$TITLE"
+version = "0.1.0"
+edition = "2021"
+"#;
+
+const LIB_SOURCE: &str = r#"#![forbid(unsafe_code)]
+
+pub fn add(x: i32, y: i32) -> i32 {
+    // compose::begin_private(unimplemented)
+    x + y
+    // compose::end_private
+}
+"#;
+
+const LIB_TESTS: &str = r#"use $CRATE::add;
+
+#[test]
+fn it_works() {
+    assert_eq!(add(2, 2), 4);
+}
+"#;
+
+const BIN_SOURCE: &str = r#"#![forbid(unsafe_code)]
+
+fn main() {
+    // compose::begin_private(unimplemented)
+    println!("Hello, world!");
+    // compose::end_private
+}
+"#;
+
+const BIN_TESTS: &str = r#"use std::process::Command;
+
+#[test]
+fn it_works() {
+    let binary = env!("CARGO_BIN_EXE_$TITLE");
+    let output = Command::new(binary).output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "Hello, world!\n");
+}
+"#;
+
+const PROC_MACRO_DEPENDENCIES: &str = r#"
+[lib]
+proc-macro = true
+
+[dependencies]
+proc-macro2 = "1.0"
+quote = "1.0"
+syn = { version = "2.0", features = ["full"] }
+"#;
+
+const PROC_MACRO_SOURCE: &str = r#"#![forbid(unsafe_code)]
+
+use proc_macro::TokenStream;
+
+#[proc_macro_derive(Describe)]
+pub fn derive_describe(input: TokenStream) -> TokenStream {
+    // compose::begin_private(unimplemented)
+    let input = syn::parse_macro_input!(input as syn::DeriveInput);
+    let name = &input.ident;
+    let description = name.to_string();
+    quote::quote! {
+        impl #name {
+            pub fn describe() -> &'static str {
+                #description
+            }
+        }
+    }
+    .into()
+    // compose::end_private
+}
+"#;
+
+const PROC_MACRO_TESTS: &str = r#"use $CRATE::Describe;
+
+#[derive(Describe)]
+struct Point;
+
+#[test]
+fn it_works() {
+    assert_eq!(Point::describe(), "Point");
+}
+"#;
+
+const ASYNC_DEPENDENCIES: &str = r#"
+[dependencies]
+tokio = { version = "1", features = ["full"] }
+"#;
+
+const ASYNC_SOURCE: &str = r#"#![forbid(unsafe_code)]
+
+use std::time::Duration;
+
+pub async fn delayed_add(x: i32, y: i32, delay: Duration) -> i32 {
+    // compose::begin_private(unimplemented)
+    tokio::time::sleep(delay).await;
+    x + y
+    // compose::end_private
+}
+"#;
+
+const ASYNC_TESTS: &str = r#"use $CRATE::delayed_add;
+use std::time::Duration;
+
+#[tokio::test]
+async fn it_works() {
+    assert_eq!(delayed_add(2, 2, Duration::from_millis(10)).await, 4);
+}
+"#;
+
+pub enum Template {
+    Lib,
+    Bin,
+    ProcMacro,
+    Async,
+}
+
+impl Template {
+    pub fn from_name(name: &str) -> Result<Self> {
+        Ok(match name {
+            "lib" => Self::Lib,
+            "bin" => Self::Bin,
+            "proc-macro" => Self::ProcMacro,
+            "async" => Self::Async,
+            name => bail!("template \"{name}\" is not supported"),
+        })
+    }
+
+    // Returns the files of the problem relative to its root.
+    pub fn files(&self, title: &str) -> Vec<(PathBuf, String)> {
+        let (source_path, source, tests, dependencies) = match self {
+            Self::Lib => ("src/lib.rs", LIB_SOURCE, LIB_TESTS, ""),
+            Self::Bin => ("src/main.rs", BIN_SOURCE, BIN_TESTS, ""),
+            Self::ProcMacro => (
+                "src/lib.rs",
+                PROC_MACRO_SOURCE,
+                PROC_MACRO_TESTS,
+                PROC_MACRO_DEPENDENCIES,
+            ),
+            Self::Async => ("src/lib.rs", ASYNC_SOURCE, ASYNC_TESTS, ASYNC_DEPENDENCIES),
+        };
+        let crate_name = title.replace('-', "_");
+        let render = |template: &str| {
+            template
+                .replace("$SOURCE", source_path)
+                .replace("$TITLE", title)
+                .replace("$CRATE", &crate_name)
+        };
+        vec![
+            (".config.yml".into(), render(CONFIG)),
+            ("Cargo.toml".into(), render(PACKAGE) + dependencies),
+            ("README.md".into(), render(README)),
+            (source_path.into(), render(source)),
+            ("tests/tests.rs".into(), render(tests)),
+        ]
+    }
+}
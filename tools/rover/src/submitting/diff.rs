@@ -0,0 +1,62 @@
+use crate::repository::repo::Repository;
+use crate::repository::solutions::SolutionsRepo;
+use anyhow::{bail, Context, Result};
+use similar::TextDiff;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+// Prints the unified diff of the solution files from the submitted ones to the working copy.
+pub fn diff_problem(problem_path: &Path, solutions_repo: Option<PathBuf>) -> Result<()> {
+    let repository = Repository::from_path(problem_path)?;
+    let problem = repository.problem_from_path(problem_path)?;
+    let settings = repository.get_settings();
+    let solutions_repo = match solutions_repo {
+        Some(path) => path,
+        None => repository.solutions_repo()?,
+    };
+    let solutions = SolutionsRepo::open(&solutions_repo)?;
+    let branch = settings.branch_name(&problem);
+    let Some(commit) = solutions.find_branch(&branch, settings)? else {
+        bail!(
+            "{} is not submitted: there's no branch {branch}",
+            problem.branch_name()
+        )
+    };
+    let config = problem.config()?;
+    let mut changed = 0;
+    for file in config.get_relative_user_files() {
+        let submitted = solutions.read_file(&commit, &problem.relative_path().join(file))?;
+        let local_path = problem.path().join(file);
+        let local = if local_path.is_file() {
+            Some(fs::read(&local_path).with_context(|| format!("failed to read {local_path:?}"))?)
+        } else {
+            None
+        };
+        if submitted == local {
+            continue;
+        }
+        let old_header = match submitted {
+            Some(_) => format!("a/{}", file.display()),
+            None => "/dev/null".to_string(),
+        };
+        let new_header = match local {
+            Some(_) => format!("b/{}", file.display()),
+            None => "/dev/null".to_string(),
+        };
+        let submitted = String::from_utf8_lossy(submitted.as_deref().unwrap_or_default());
+        let local = String::from_utf8_lossy(local.as_deref().unwrap_or_default());
+        print!(
+            "{}",
+            TextDiff::from_lines(&submitted, &local)
+                .unified_diff()
+                .header(&old_header, &new_header)
+        );
+        changed += 1;
+    }
+    if changed == 0 {
+        eprintln!("No changes since the last submission to {branch}");
+    }
+    Ok(())
+}
@@ -0,0 +1,59 @@
+use crate::{
+    repository::{context::LaunchOptions, problem::Problem},
+    testing::format::ReportFormat,
+};
+use anyhow::{bail, Result};
+use std::{fs, io};
+
+const LINTERS_STEP: &str = "linters";
+// Left by `rover compose` in place of the solution.
+const UNFINISHED_MARKERS: [&str; 2] = ["unimplemented!()", "TODO: your code goes here."];
+
+// Only warns, as the markers may be left on purpose in the parts that are not graded.
+pub fn warn_unfinished(problem: &Problem) -> Result<()> {
+    let config = problem.config()?;
+    let files = config
+        .get_relative_user_files()
+        .iter()
+        .zip(config.get_absolute_user_files());
+    for (relative, absolute) in files {
+        let Ok(content) = fs::read_to_string(absolute) else {
+            continue;
+        };
+        for (index, line) in content.lines().enumerate() {
+            for marker in UNFINISHED_MARKERS {
+                if line.contains(marker) {
+                    eprintln!(
+                        "warning: {}:{}: \"{marker}\" is left, the solution may be unfinished",
+                        relative.display(),
+                        index + 1
+                    );
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// Launches the linters of the problem, or all the steps, and fails if any of them fails.
+pub fn check_before_submit(problem: &Problem, all_steps: bool) -> Result<()> {
+    let only = if all_steps {
+        None
+    } else if problem
+        .config()?
+        .get_steps()
+        .iter()
+        .any(|step| step.name() == LINTERS_STEP)
+    {
+        Some(LINTERS_STEP)
+    } else {
+        return Ok(());
+    };
+    println!("Checking {} before submitting", problem.branch_name());
+    let outcome = problem.launch_steps(&LaunchOptions::new(true, 1, true), only)?;
+    if outcome.failed() {
+        ReportFormat::Human.write(&outcome, &mut io::stderr())?;
+        bail!("checks failed, fix them or submit with --force");
+    }
+    Ok(())
+}
@@ -1 +1,5 @@
+pub(crate) mod diff;
+mod gate;
+pub(crate) mod restore;
+pub(crate) mod status;
 pub(crate) mod submit;
@@ -0,0 +1,80 @@
+use crate::repository::solutions::SolutionsRepo;
+use crate::repository::{problem::Problem, repo::Repository, settings::Settings};
+use anyhow::{bail, Context, Result};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+// Writes the submitted solution files of the problem to the course repository, the other files
+// of the branch may be stale copies of the course. Returns the number of restored files, or None
+// if there's no branch.
+fn restore_files(
+    problem: &Problem,
+    solutions: &SolutionsRepo,
+    settings: &Settings,
+    from_branch: Option<&str>,
+) -> Result<Option<usize>> {
+    let branch = match from_branch {
+        Some(branch) => branch.to_string(),
+        None => settings.branch_name(problem),
+    };
+    let Some(commit) = solutions.find_branch(&branch, settings)? else {
+        return Ok(None);
+    };
+    let relative_path = problem.relative_path();
+    let config = problem.config()?;
+    let mut restored = 0;
+    for file in config.get_relative_user_files() {
+        let Some(content) = solutions.read_file(&commit, &relative_path.join(file))? else {
+            continue;
+        };
+        let path = problem.path().join(file);
+        fs::create_dir_all(path.parent().unwrap())
+            .with_context(|| format!("failed to create directory for {path:?}"))?;
+        fs::write(&path, content).with_context(|| format!("failed to write {path:?}"))?;
+        println!(
+            "restored {} from {branch}",
+            relative_path.join(file).display()
+        );
+        restored += 1;
+    }
+    Ok(Some(restored))
+}
+
+pub fn restore_problems(
+    path: &Path,
+    all: bool,
+    from_branch: Option<&str>,
+    solutions_repo: Option<PathBuf>,
+) -> Result<()> {
+    let repository = Repository::from_path(path)?;
+    let settings = repository.get_settings();
+    let solutions_repo = match solutions_repo {
+        Some(path) => path,
+        None => repository.solutions_repo()?,
+    };
+    let solutions = SolutionsRepo::open(&solutions_repo)?;
+    if !all {
+        let problem = repository.problem_from_path(path)?;
+        let branch = from_branch
+            .map(str::to_string)
+            .unwrap_or_else(|| settings.branch_name(&problem));
+        match restore_files(&problem, &solutions, settings, from_branch)? {
+            None => bail!("there's no branch {branch} in the solutions repository"),
+            Some(0) => bail!(
+                "branch {branch} has no solution files of {}",
+                problem.relative_path().display()
+            ),
+            Some(_) => return Ok(()),
+        }
+    }
+    let mut restored = 0;
+    for problem in repository.problems(None)? {
+        if restore_files(&problem, &solutions, settings, from_branch)?.is_some_and(|n| n > 0) {
+            restored += 1;
+        }
+    }
+    println!("Restored {restored} problem(s)");
+    Ok(())
+}
@@ -0,0 +1,126 @@
+use crate::{
+    repository::{
+        problem::Problem, repo::Repository, settings::Settings, solutions::SolutionsRepo,
+    },
+    util::table::write_table,
+};
+use anyhow::{Context, Result};
+use chrono::{DateTime, FixedOffset};
+use git2::Commit;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+const NOT_SUBMITTED: &str = "-";
+
+// Compares the allowed files with their submitted versions.
+fn local_files_state(
+    solutions: &SolutionsRepo,
+    commit: &Commit,
+    problem: &Problem,
+) -> Result<&'static str> {
+    let config = problem.config()?;
+    let files = config
+        .get_relative_user_files()
+        .iter()
+        .zip(config.get_absolute_user_files());
+    for (relative, absolute) in files {
+        let submitted = solutions.read_file(commit, &problem.relative_path().join(relative))?;
+        let local = fs::read(absolute).ok();
+        if submitted.is_none() || submitted != local {
+            return Ok("changed");
+        }
+    }
+    Ok("unchanged")
+}
+
+// In the time zone of the committer, like `git log`.
+fn commit_time(commit: &Commit) -> String {
+    let time = commit.time();
+    FixedOffset::east_opt(time.offset_minutes() * 60)
+        .zip(DateTime::from_timestamp(time.seconds(), 0))
+        .map(|(offset, time)| {
+            time.with_timezone(&offset)
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        })
+        .unwrap_or_else(|| NOT_SUBMITTED.to_string())
+}
+
+fn problem_row(
+    solutions: &SolutionsRepo,
+    settings: &Settings,
+    problem: &Problem,
+) -> Result<Vec<String>> {
+    let branch = settings.branch_name(problem);
+    let Some(commit) = solutions.find_branch(&branch, settings)? else {
+        return Ok(vec![
+            problem.branch_name(),
+            branch,
+            "no".to_string(),
+            NOT_SUBMITTED.to_string(),
+            NOT_SUBMITTED.to_string(),
+            NOT_SUBMITTED.to_string(),
+        ]);
+    };
+    let files = local_files_state(solutions, &commit, problem)?;
+    let (time, message) = match solutions.last_change(&commit, &problem.relative_path())? {
+        Some(change) => (
+            commit_time(&change),
+            change.summary().unwrap_or_default().to_string(),
+        ),
+        None => (NOT_SUBMITTED.to_string(), NOT_SUBMITTED.to_string()),
+    };
+    Ok(vec![
+        problem.branch_name(),
+        branch,
+        "yes".to_string(),
+        files.to_string(),
+        time,
+        message,
+    ])
+}
+
+pub fn print_status(
+    path: &Path,
+    group: Option<&str>,
+    solutions_repo: Option<PathBuf>,
+) -> Result<()> {
+    let repository = Repository::from_path(path)?;
+    let solutions_repo = match solutions_repo {
+        Some(path) => path,
+        None => repository.solutions_repo()?,
+    };
+    let settings = repository.get_settings();
+    let solutions = SolutionsRepo::open(&solutions_repo)?;
+    let mut rows = vec![];
+    let mut errors = vec![];
+    for problem in repository.problems(group)? {
+        match problem_row(&solutions, settings, &problem) {
+            Ok(row) => rows.push(row),
+            Err(err) => {
+                let mut row = vec![problem.branch_name(), settings.branch_name(&problem)];
+                row.extend(
+                    ["error", NOT_SUBMITTED, NOT_SUBMITTED, NOT_SUBMITTED].map(str::to_string),
+                );
+                rows.push(row);
+                errors.push(format!("{}: {err:#}", problem.branch_name()));
+            }
+        }
+    }
+    let header = [
+        "problem",
+        "branch",
+        "submitted",
+        "local files",
+        "last submission",
+        "message",
+    ]
+    .map(str::to_string);
+    write_table(&header, &rows, &mut io::stdout()).context("failed to write status")?;
+    for error in errors {
+        println!("{error}");
+    }
+    Ok(())
+}
@@ -1,38 +1,32 @@
-use crate::{launch_git, repository::repo::Repository};
-use anyhow::{bail, Result};
-use std::{
-    path::{Path, PathBuf},
-    process,
-};
+use super::gate::{check_before_submit, warn_unfinished};
+use crate::repository::{repo::Repository, solutions::SolutionsRepo};
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
 
 pub fn submit_problem(
     problem_path: &Path,
     message: &str,
     solutions_repo: Option<PathBuf>,
+    all_steps: bool,
+    force: bool,
 ) -> Result<()> {
     let repository = Repository::from_path(problem_path)?;
     let problem = repository.problem_from_path(problem_path)?;
+    warn_unfinished(&problem)?;
+    if !force {
+        check_before_submit(&problem, all_steps)?;
+    }
     let solutions_repo = match solutions_repo {
         Some(path) => path,
         None => repository.solutions_repo()?,
     };
-    problem.move_solution_files_to(&solutions_repo)?;
-    if !launch_git!(&solutions_repo, "add", ".") {
-        bail!("git add failed");
-    }
-    if !launch_git!(&solutions_repo, "commit", "-m", message) {
-        bail!("git commit failed: either no changes since the last commit or git failed")
-    }
-    if !launch_git!(&solutions_repo, "push")
-        && !launch_git!(
-            &solutions_repo,
-            "push",
-            "--set-upstream",
-            "origin",
-            problem.branch_name()
-        )
-    {
-        bail!("git push failed")
-    }
+    let settings = repository.get_settings();
+    problem.move_solution_files_to(&solutions_repo, settings)?;
+    let branch_name = settings.branch_name(&problem);
+    let repo = SolutionsRepo::open(&solutions_repo)?;
+    repo.add_all().context("failed to stage solution files")?;
+    repo.commit(message).context("failed to commit solution")?;
+    repo.push(settings.remote(), &branch_name)
+        .with_context(|| format!("failed to push {branch_name}"))?;
     Ok(())
 }
@@ -0,0 +1,388 @@
+use crate::{
+    repository::{
+        outcome::{CommandOutcome, Status, StepOutcome, TestOutcome},
+        scoring::Score,
+    },
+    util::table::write_table,
+};
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use std::io::Write;
+
+const OUTPUT_TAIL_LINES: usize = 50;
+
+pub enum ReportFormat {
+    Human,
+    Json,
+    Junit,
+}
+
+#[derive(Serialize)]
+struct TestRecord<'a> {
+    problem: &'a str,
+    status: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<&'a str>,
+    duration: f64,
+    score: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tests_passed: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tests_total: Option<usize>,
+    steps: Vec<StepRecord<'a>>,
+}
+
+#[derive(Serialize)]
+struct StepRecord<'a> {
+    name: &'a str,
+    status: &'a str,
+    duration: f64,
+    commands: Vec<CommandRecord<'a>>,
+}
+
+#[derive(Serialize)]
+struct CommandRecord<'a> {
+    name: &'a str,
+    status: &'a str,
+    shell_line: Option<&'a str>,
+    toolchain: &'a str,
+    duration: f64,
+    exit_code: Option<i32>,
+    stdout_tail: String,
+    stderr_tail: String,
+    failure: Option<&'a str>,
+    failure_kind: Option<&'a str>,
+    cached: bool,
+}
+
+impl<'a> From<&'a CommandOutcome> for CommandRecord<'a> {
+    fn from(command: &'a CommandOutcome) -> Self {
+        Self {
+            name: command.name(),
+            status: command.status().name(),
+            shell_line: command.shell_line(),
+            toolchain: command.toolchain(),
+            duration: command.duration().as_secs_f64(),
+            exit_code: command.exit_code(),
+            stdout_tail: tail(command.stdout()),
+            stderr_tail: tail(command.stderr()),
+            failure: command.failure(),
+            failure_kind: command.failure_kind(),
+            cached: command.is_cached(),
+        }
+    }
+}
+
+impl<'a> From<&'a TestOutcome> for TestRecord<'a> {
+    fn from(outcome: &'a TestOutcome) -> Self {
+        Self {
+            problem: outcome.problem(),
+            status: if outcome.failed() {
+                Status::Failed.name()
+            } else {
+                Status::Passed.name()
+            },
+            error: outcome.error(),
+            duration: outcome.duration().as_secs_f64(),
+            score: outcome.score(),
+            tests_passed: outcome.test_score().map(Score::passed),
+            tests_total: outcome.test_score().map(Score::total),
+            steps: outcome
+                .steps()
+                .iter()
+                .map(|step| StepRecord {
+                    name: step.name(),
+                    status: step.status().name(),
+                    duration: step.duration().as_secs_f64(),
+                    commands: step.commands().iter().map(CommandRecord::from).collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl ReportFormat {
+    pub fn from_name(name: &str) -> Result<Self> {
+        Ok(match name {
+            "human" => Self::Human,
+            "json" => Self::Json,
+            "junit" => Self::Junit,
+            name => bail!("report format \"{name}\" is not supported"),
+        })
+    }
+
+    pub fn echo_output(&self) -> bool {
+        matches!(self, Self::Human)
+    }
+
+    pub fn write(&self, outcome: &TestOutcome, writer: &mut dyn Write) -> Result<()> {
+        match self {
+            Self::Human => write_human(outcome, writer),
+            Self::Json => write_json(&TestRecord::from(outcome), writer),
+            Self::Junit => write_junit(std::slice::from_ref(outcome), writer),
+        }
+        .context("failed to write report")
+    }
+
+    pub fn write_batch(&self, outcomes: &[TestOutcome], writer: &mut dyn Write) -> Result<()> {
+        match self {
+            Self::Human => write_summary(outcomes, writer),
+            Self::Json => write_json(
+                &outcomes.iter().map(TestRecord::from).collect::<Vec<_>>(),
+                writer,
+            ),
+            Self::Junit => write_junit(outcomes, writer),
+        }
+        .context("failed to write report")
+    }
+}
+
+pub fn json_record(outcome: &TestOutcome) -> Result<String> {
+    serde_json::to_string(&TestRecord::from(outcome)).context("failed to serialize report")
+}
+
+fn write_json(record: &impl Serialize, writer: &mut dyn Write) -> Result<()> {
+    serde_json::to_writer_pretty(&mut *writer, record).context("failed to serialize report")?;
+    writeln!(writer)?;
+    Ok(())
+}
+
+fn tail(output: &str) -> String {
+    let lines: Vec<_> = output.lines().collect();
+    let skip = lines.len().saturating_sub(OUTPUT_TAIL_LINES);
+    lines[skip..].join("\n")
+}
+
+fn write_human(outcome: &TestOutcome, writer: &mut dyn Write) -> Result<()> {
+    writeln!(writer, "Testing results of {}:", outcome.problem())?;
+    if let Some(error) = outcome.error() {
+        writeln!(writer, "  error: {error}")?;
+    }
+    for step in outcome.steps() {
+        writeln!(writer, "  {}: {}", step.name(), step.status().name())?;
+        for command in step.commands() {
+            write!(
+                writer,
+                "    {} ... {}",
+                command.name(),
+                command.status().name()
+            )?;
+            if command.is_cached() {
+                write!(writer, " (cached)")?;
+            } else if command.status() != Status::Skipped {
+                write!(writer, " ({:.2}s)", command.duration().as_secs_f64())?;
+            }
+            writeln!(writer)?;
+            if let Some(failure) = command.failure() {
+                writeln!(
+                    writer,
+                    "      reason: {}",
+                    failure.replace('\n', "\n        ")
+                )?;
+            }
+        }
+    }
+    writeln!(writer, "  score: {}", format_score(outcome))?;
+    Ok(())
+}
+
+fn format_score(outcome: &TestOutcome) -> String {
+    match outcome.test_score() {
+        Some(score) => format!(
+            "{:.2} ({} of {} tests passed)",
+            score.value(),
+            score.passed(),
+            score.total()
+        ),
+        None => format!("{:.2}", outcome.score()),
+    }
+}
+
+fn write_summary(outcomes: &[TestOutcome], writer: &mut dyn Write) -> Result<()> {
+    let mut steps: Vec<&str> = vec![];
+    for outcome in outcomes {
+        for step in outcome.steps() {
+            if !steps.contains(&step.name()) {
+                steps.push(step.name());
+            }
+        }
+    }
+    let rows: Vec<Vec<String>> = outcomes
+        .iter()
+        .map(|outcome| {
+            let mut row = vec![outcome.problem().to_string()];
+            for name in &steps {
+                row.push(
+                    match outcome.steps().iter().find(|step| step.name() == *name) {
+                        Some(step) if step.status() == Status::Skipped => {
+                            step.status().name().into()
+                        }
+                        Some(step) => format!(
+                            "{} {:.1}s",
+                            step.status().name(),
+                            step.duration().as_secs_f64()
+                        ),
+                        None if outcome.error().is_some() => "error".into(),
+                        None => "-".into(),
+                    },
+                );
+            }
+            row.push(format!("{:.2}", outcome.score()));
+            row.push(format!("{:.1}s", outcome.duration().as_secs_f64()));
+            row
+        })
+        .collect();
+    let header: Vec<String> = std::iter::once("problem")
+        .chain(steps.iter().copied())
+        .chain(["score", "total"])
+        .map(str::to_string)
+        .collect();
+    write_table(&header, &rows, writer)?;
+    for outcome in outcomes {
+        if let Some(error) = outcome.error() {
+            writeln!(writer, "{}: {error}", outcome.problem())?;
+        }
+    }
+    let failed = outcomes.iter().filter(|outcome| outcome.failed()).count();
+    writeln!(
+        writer,
+        "{} of {} problems passed",
+        outcomes.len() - failed,
+        outcomes.len()
+    )?;
+    Ok(())
+}
+
+fn write_junit(outcomes: &[TestOutcome], writer: &mut dyn Write) -> Result<()> {
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    let (name, duration) = match outcomes {
+        [outcome] => (outcome.problem(), outcome.duration()),
+        outcomes => ("rover", outcomes.iter().map(TestOutcome::duration).sum()),
+    };
+    writeln!(
+        writer,
+        r#"<testsuites name="{}" time="{:.3}">"#,
+        escape_xml(name),
+        duration.as_secs_f64()
+    )?;
+    for outcome in outcomes {
+        if let Some(error) = outcome.error() {
+            writeln!(
+                writer,
+                r#"  <testsuite name="{}" tests="1" failures="0" errors="1" time="0.000">"#,
+                escape_xml(outcome.problem())
+            )?;
+            writeln!(
+                writer,
+                r#"    <testcase name="config" classname="{}"><error message="{}"/></testcase>"#,
+                escape_xml(outcome.problem()),
+                escape_xml(error)
+            )?;
+            writeln!(writer, "  </testsuite>")?;
+        }
+        for step in outcome.steps() {
+            let suite_name = match outcomes {
+                [_] => step.name().to_string(),
+                _ => format!("{}/{}", outcome.problem(), step.name()),
+            };
+            write_junit_suite(outcome, step, &suite_name, writer)?;
+        }
+    }
+    writeln!(writer, "</testsuites>")?;
+    Ok(())
+}
+
+fn write_junit_suite(
+    outcome: &TestOutcome,
+    step: &StepOutcome,
+    suite_name: &str,
+    writer: &mut dyn Write,
+) -> Result<()> {
+    let count = |status| {
+        step.commands()
+            .iter()
+            .filter(|command| command.status() == status)
+            .count()
+    };
+    writeln!(
+        writer,
+        r#"  <testsuite name="{}" tests="{}" failures="{}" skipped="{}" time="{:.3}">"#,
+        escape_xml(suite_name),
+        step.commands().len(),
+        count(Status::Failed),
+        count(Status::Skipped),
+        step.duration().as_secs_f64()
+    )?;
+    // The shell line and the toolchain of every command, since test cases have no properties.
+    let commands = step
+        .commands()
+        .iter()
+        .filter_map(|command| Some((command, command.shell_line()?)))
+        .collect::<Vec<_>>();
+    if !commands.is_empty() {
+        writeln!(writer, "    <properties>")?;
+        for (command, shell_line) in commands {
+            writeln!(
+                writer,
+                r#"      <property name="{}.shell-line" value="{}"/>"#,
+                escape_xml(command.name()),
+                escape_xml(shell_line)
+            )?;
+            writeln!(
+                writer,
+                r#"      <property name="{}.toolchain" value="{}"/>"#,
+                escape_xml(command.name()),
+                escape_xml(command.toolchain())
+            )?;
+        }
+        writeln!(writer, "    </properties>")?;
+    }
+    for command in step.commands() {
+        writeln!(
+            writer,
+            r#"    <testcase name="{}" classname="{}.{}" time="{:.3}">"#,
+            escape_xml(command.name()),
+            escape_xml(outcome.problem()),
+            escape_xml(step.name()),
+            command.duration().as_secs_f64()
+        )?;
+        match command.status() {
+            Status::Passed => {}
+            Status::Skipped => writeln!(writer, "      <skipped/>")?,
+            Status::Failed => writeln!(
+                writer,
+                r#"      <failure message="{}"/>"#,
+                escape_xml(command.failure().unwrap_or_default())
+            )?,
+        }
+        writeln!(
+            writer,
+            "      <system-out>{}</system-out>",
+            escape_xml(&tail(command.stdout()))
+        )?;
+        writeln!(
+            writer,
+            "      <system-err>{}</system-err>",
+            escape_xml(&tail(command.stderr()))
+        )?;
+        writeln!(writer, "    </testcase>")?;
+    }
+    writeln!(writer, "  </testsuite>")?;
+    Ok(())
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c if c.is_control() && c != '\n' && c != '\t' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
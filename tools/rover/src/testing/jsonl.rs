@@ -0,0 +1,33 @@
+use super::{
+    format::json_record,
+    report::{JsonlConfig, Reporter},
+};
+use crate::repository::outcome::TestOutcome;
+use anyhow::{Context, Result};
+use std::{fs::OpenOptions, io::Write};
+
+pub struct JsonlReporter {
+    config: JsonlConfig,
+}
+
+impl JsonlReporter {
+    pub fn new(config: JsonlConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl Reporter for JsonlReporter {
+    fn push_report(&self, outcomes: &[TestOutcome]) -> Result<()> {
+        let path = &self.config.path;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("failed to open {path:?}"))?;
+        for outcome in outcomes {
+            writeln!(file, "{}", json_record(outcome)?)
+                .with_context(|| format!("failed to write to {path:?}"))?;
+        }
+        Ok(())
+    }
+}
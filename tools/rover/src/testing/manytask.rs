@@ -0,0 +1,84 @@
+use super::report::{check_response, render_template, ManytaskConfig, Reporter};
+use crate::repository::outcome::TestOutcome;
+use anyhow::{Context, Result};
+use reqwest::blocking::{multipart::Form, Client};
+use std::env;
+
+const REPORT_PATH: &str = "api/report";
+
+pub struct ManytaskReporter {
+    config: ManytaskConfig,
+}
+
+impl ManytaskReporter {
+    pub fn new(config: ManytaskConfig) -> Self {
+        Self { config }
+    }
+
+    fn variable(name: &str) -> Result<String> {
+        env::var(name).with_context(|| format!("no {name} variable"))
+    }
+
+    // The task is the second part of the "GROUP/TITLE" branch name. Outside of CI, the problem
+    // name is used, so a batch run reports every task.
+    fn task_name(&self, outcome: &TestOutcome) -> Result<String> {
+        let (source, branch) = match env::var(&self.config.task_variable) {
+            Ok(branch) => (self.config.task_variable.as_str(), branch),
+            Err(_) => ("problem name", outcome.problem().to_string()),
+        };
+        Ok(branch
+            .split('/')
+            .nth(1)
+            .with_context(|| format!("{source} does not contain '/' symbol"))?
+            .to_owned())
+    }
+
+    fn fields(
+        &self,
+        outcome: &TestOutcome,
+        user_id: &str,
+        token: &str,
+    ) -> Result<Vec<(String, String)>> {
+        let mut fields = vec![
+            ("user_id".to_string(), user_id.to_string()),
+            ("task".to_string(), self.task_name(outcome)?),
+            ("token".to_string(), token.to_string()),
+        ];
+        if outcome.failed() {
+            fields.push((self.config.failed_field.clone(), "1".to_string()));
+        }
+        if let Some(score_field) = &self.config.score_field {
+            fields.push((score_field.clone(), outcome.score().to_string()));
+        }
+        for (name, template) in &self.config.fields {
+            fields.push((name.clone(), render_template(template, outcome)?));
+        }
+        Ok(fields)
+    }
+}
+
+impl Reporter for ManytaskReporter {
+    fn push_report(&self, outcomes: &[TestOutcome]) -> Result<()> {
+        if env::var("SKIP_REPORT").is_ok() {
+            return Ok(());
+        }
+        let user_id = Self::variable(&self.config.user_id_variable)?;
+        let token = Self::variable(&self.config.token_variable)?;
+        let url = format!(
+            "{}/{REPORT_PATH}",
+            self.config.base_url.trim_end_matches('/')
+        );
+        let client = Client::new();
+        for outcome in outcomes {
+            let fields = self.fields(outcome, &user_id, &token)?;
+            let response = self.config.retry.send(|| {
+                let form = fields.iter().fold(Form::new(), |form, (name, value)| {
+                    form.text(name.clone(), value.clone())
+                });
+                client.post(&url).multipart(form)
+            });
+            check_response(response.context("post report to manytask")?)?;
+        }
+        Ok(())
+    }
+}
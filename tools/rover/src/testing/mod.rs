@@ -1,2 +1,8 @@
+pub mod format;
+pub mod jsonl;
+pub mod manytask;
 pub mod report;
+pub mod server;
 pub mod test;
+pub mod watch;
+pub mod webhook;
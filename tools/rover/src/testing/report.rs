@@ -1,69 +1,219 @@
+use super::{
+    format::json_record, jsonl::JsonlReporter, manytask::ManytaskReporter, webhook::WebhookReporter,
+};
+use crate::repository::outcome::TestOutcome;
 use anyhow::{bail, Context, Result};
-use reqwest::blocking::{multipart::Form, Client};
-use std::{env, thread, time};
+use reqwest::blocking::{RequestBuilder, Response};
+use serde::Deserialize;
+use std::{collections::BTreeMap, env, fs, path::Path, thread, time::Duration};
 
-const MANYTASK_URL: &str = "https://mipt-rust.manytask.org/api/report";
-const MANYTASK_RETRIES: usize = 3;
+pub trait Reporter {
+    fn push_report(&self, outcomes: &[TestOutcome]) -> Result<()>;
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ReportConfig {
+    manytask: ManytaskConfig,
+    webhook: Option<WebhookConfig>,
+    jsonl: Option<JsonlConfig>,
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ManytaskConfig {
+    pub base_url: String,
+    pub task_variable: String,
+    pub user_id_variable: String,
+    pub token_variable: String,
+    pub failed_field: String,
+    pub score_field: Option<String>,
+    pub fields: BTreeMap<String, String>,
+    pub retry: RetryPolicy,
+}
+
+impl Default for ManytaskConfig {
+    fn default() -> Self {
+        Self {
+            base_url: "https://mipt-rust.manytask.org".to_string(),
+            task_variable: "CI_COMMIT_REF_NAME".to_string(),
+            user_id_variable: "GITLAB_USER_ID".to_string(),
+            token_variable: "TESTER_TOKEN".to_string(),
+            failed_field: "failed".to_string(),
+            score_field: None,
+            fields: BTreeMap::new(),
+            retry: RetryPolicy::default(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct WebhookConfig {
+    pub url: String,
+    #[serde(default = "default_method")]
+    pub method: String,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default = "default_body")]
+    pub body: String,
+    #[serde(default)]
+    pub retry: RetryPolicy,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct JsonlConfig {
+    pub path: String,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct RetryPolicy {
+    attempts: usize,
+    backoff_ms: u64,
+    factor: u32,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: 3,
+            backoff_ms: 1000,
+            factor: 1,
+        }
+    }
+}
+
+fn default_method() -> String {
+    "POST".to_string()
+}
 
-pub enum ReportType {
-    NoReport,
-    Manytask,
+fn default_body() -> String {
+    "{report}".to_string()
 }
 
-impl ReportType {
-    pub fn from_name(name: &str) -> Result<Self> {
-        Ok(match name {
-            "no-report" => Self::NoReport,
-            "manytask" => Self::Manytask,
-            name => bail!("report type \"{name}\" is not supported"),
-        })
+impl ReportConfig {
+    pub fn from_file(path: Option<&Path>) -> Result<Self> {
+        let Some(path) = path else {
+            return Ok(Self::default());
+        };
+        let content =
+            fs::read_to_string(path).with_context(|| format!("failed to read {path:?}"))?;
+        serde_yaml::from_str(&content).with_context(|| format!("failed to parse {path:?}"))
     }
+}
+
+pub fn reporter_from_name(name: &str, config: ReportConfig) -> Result<Box<dyn Reporter>> {
+    Ok(match name {
+        "no-report" => Box::new(NoReport),
+        "manytask" => Box::new(ManytaskReporter::new(config.manytask)),
+        "webhook" => Box::new(WebhookReporter::new(
+            config
+                .webhook
+                .context("report config has no \"webhook\" section")?,
+        )),
+        "jsonl" => Box::new(JsonlReporter::new(
+            config
+                .jsonl
+                .context("report config has no \"jsonl\" section")?,
+        )),
+        name => bail!("report type \"{name}\" is not supported"),
+    })
+}
 
-    pub fn push_report(&self, failed: bool) -> Result<()> {
-        match self {
-            Self::NoReport => {
-                if failed {
-                    bail!("testing failed")
-                } else {
-                    Ok(())
-                }
+struct NoReport;
+
+impl Reporter for NoReport {
+    fn push_report(&self, _: &[TestOutcome]) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl RetryPolicy {
+    // Retries on connection errors and 5xx codes. Other codes are returned to the caller.
+    pub fn send(&self, request: impl Fn() -> RequestBuilder) -> Result<Response> {
+        let attempts = self.attempts.max(1);
+        let mut delay = Duration::from_millis(self.backoff_ms);
+        let mut last_error = None;
+        for attempt in 1..=attempts {
+            match request().send() {
+                Ok(response) if !response.status().is_server_error() => return Ok(response),
+                Ok(response) => last_error = Some(format!("code {}", response.status())),
+                Err(err) => last_error = Some(err.to_string()),
             }
-            Self::Manytask => {
-                if env::var("SKIP_REPORT").is_ok() {
-                    return Ok(());
-                }
-                let task_name = env::var("CI_COMMIT_REF_NAME")
-                    .context("no CI_COMMIT_REF_NAME variable")?
-                    .split('/')
-                    .nth(1)
-                    .context("CI_COMMIT_REF_NAME does not contain '/' symbol")?
-                    .to_owned();
-                let user_id = env::var("GITLAB_USER_ID").context("no GITLAB_USER_ID variable")?;
-                let tester_token = env::var("TESTER_TOKEN").context("no TESTER_TOKEN variable")?;
-                let client = Client::new();
-                for _ in 0..MANYTASK_RETRIES {
-                    let mut data = Form::new()
-                        .text("user_id", user_id.clone())
-                        .text("task", task_name.clone())
-                        .text("token", tester_token.clone());
-                    if failed {
-                        data = data.text("failed", "1");
-                    }
-                    if client
-                        .post(MANYTASK_URL)
-                        .multipart(data)
-                        .send()
-                        .context("post report to manytask")?
-                        .status()
-                        != 500
-                    {
-                        return Ok(());
-                    } else {
-                        thread::sleep(time::Duration::from_millis(1000));
-                    }
-                }
-                bail!("{MANYTASK_RETRIES} posts to manytask gave 500 code")
+            if attempt < attempts {
+                thread::sleep(delay);
+                delay *= self.factor.max(1);
             }
         }
+        bail!(
+            "{attempts} attempt(s) to send the report failed, the last one with {}",
+            last_error.unwrap_or_default()
+        )
     }
 }
+
+pub fn check_response(response: Response) -> Result<()> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    let body = response.text().unwrap_or_default();
+    bail!("report was rejected with code {status}: {}", body.trim())
+}
+
+// Replaces "{name}" placeholders and leaves any other braces intact, so JSON templates need
+// no escaping.
+pub fn render_template(template: &str, outcome: &TestOutcome) -> Result<String> {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let placeholder_end = rest
+            .find('}')
+            .map(|end| (&rest[1..end], end))
+            .filter(|(name, _)| is_placeholder(name));
+        match placeholder_end {
+            Some((name, end)) => {
+                rendered.push_str(&placeholder(name, outcome)?);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                rendered.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    rendered.push_str(rest);
+    Ok(rendered)
+}
+
+fn is_placeholder(name: &str) -> bool {
+    matches!(
+        name,
+        "problem" | "group" | "title" | "status" | "failed" | "score" | "duration" | "report"
+    ) || name.starts_with("env:")
+}
+
+fn placeholder(name: &str, outcome: &TestOutcome) -> Result<String> {
+    let (group, title) = outcome
+        .problem()
+        .split_once('/')
+        .unwrap_or(("", outcome.problem()));
+    Ok(match name {
+        "problem" => outcome.problem().to_string(),
+        "group" => group.to_string(),
+        "title" => title.to_string(),
+        "status" => if outcome.failed() { "failed" } else { "passed" }.to_string(),
+        "failed" => outcome.failed().to_string(),
+        "score" => outcome.score().to_string(),
+        "duration" => format!("{:.3}", outcome.duration().as_secs_f64()),
+        "report" => json_record(outcome)?,
+        name => {
+            let variable = name.trim_start_matches("env:");
+            env::var(variable).with_context(|| format!("no {variable} variable"))?
+        }
+    })
+}
@@ -0,0 +1,132 @@
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+};
+
+#[derive(Serialize)]
+struct Request {
+    method: String,
+    path: String,
+    headers: BTreeMap<String, String>,
+    body: String,
+}
+
+pub struct ServerOptions {
+    pub status: u16,
+    pub fail_first: usize,
+    pub max_requests: Option<usize>,
+}
+
+// A stand-in for report receivers: prints every request as a JSON line and answers with a fixed
+// code. The first requests may be answered with 500 to check retries.
+pub fn run_report_server(address: &str, options: &ServerOptions) -> Result<()> {
+    let listener =
+        TcpListener::bind(address).with_context(|| format!("failed to bind to {address}"))?;
+    eprintln!(
+        "Listening on {}",
+        listener.local_addr().context("no local address")?
+    );
+    let mut served = 0;
+    for stream in listener.incoming() {
+        if options.max_requests.is_some_and(|max| served >= max) {
+            break;
+        }
+        let mut stream = stream.context("failed to accept connection")?;
+        let request = match read_request(&mut stream) {
+            Ok(request) => request,
+            Err(err) => {
+                eprintln!("Bad request: {err:#}");
+                respond(&mut stream, 400)?;
+                continue;
+            }
+        };
+        println!(
+            "{}",
+            serde_json::to_string(&request).context("failed to serialize request")?
+        );
+        served += 1;
+        let status = if served <= options.fail_first {
+            500
+        } else {
+            options.status
+        };
+        respond(&mut stream, status)?;
+        if options.max_requests.is_some_and(|max| served >= max) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn read_request(stream: &mut TcpStream) -> Result<Request> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        bail!("invalid request line \"{}\"", line.trim())
+    };
+    let (method, path) = (method.to_string(), path.to_string());
+    let mut headers = BTreeMap::new();
+    loop {
+        line.clear();
+        reader.read_line(&mut line)?;
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        let (name, value) = header
+            .split_once(':')
+            .with_context(|| format!("invalid header \"{header}\""))?;
+        headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+    }
+    let body = if headers
+        .get("transfer-encoding")
+        .is_some_and(|encoding| encoding.eq_ignore_ascii_case("chunked"))
+    {
+        read_chunked(&mut reader)?
+    } else {
+        let length: usize = match headers.get("content-length") {
+            Some(length) => length.parse().context("invalid content length")?,
+            None => 0,
+        };
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body)?;
+        body
+    };
+    Ok(Request {
+        method,
+        path,
+        headers,
+        body: String::from_utf8_lossy(&body).into_owned(),
+    })
+}
+
+fn read_chunked(reader: &mut impl BufRead) -> Result<Vec<u8>> {
+    let mut body = vec![];
+    let mut line = String::new();
+    loop {
+        line.clear();
+        reader.read_line(&mut line)?;
+        let size = usize::from_str_radix(line.trim().split(';').next().unwrap_or_default(), 16)
+            .context("invalid chunk size")?;
+        let mut chunk = vec![0; size + 2];
+        reader.read_exact(&mut chunk)?;
+        if size == 0 {
+            return Ok(body);
+        }
+        body.extend_from_slice(&chunk[..size]);
+    }
+}
+
+fn respond(stream: &mut TcpStream, status: u16) -> Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {status} {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        if status < 400 { "OK" } else { "Error" }
+    )
+    .context("failed to write response")
+}
@@ -1,6 +1,35 @@
-use crate::repository::problem::Problem;
-use anyhow::Result;
+use crate::repository::{context::LaunchOptions, outcome::TestOutcome, problem::Problem};
+use std::path::Path;
 
-pub fn test_problem(problem: Problem) -> Result<()> {
-    problem.launch_all_steps()
+// A problem that can't be launched or fails verification is reported as errored, like a failed
+// one.
+pub fn test_problem(
+    problem: Problem,
+    options: &LaunchOptions,
+    verify: Option<&Path>,
+) -> TestOutcome {
+    let name = problem.branch_name();
+    let verified = match verify {
+        Some(course) => problem.verify_integrity(course),
+        None => Ok(()),
+    };
+    verified
+        .and_then(|()| problem.launch_all_steps(options))
+        .unwrap_or_else(|err| TestOutcome::errored(&name, err))
+}
+
+pub fn test_problems(
+    problems: Vec<Problem>,
+    options: &LaunchOptions,
+    verify: Option<&Path>,
+) -> Vec<TestOutcome> {
+    problems
+        .into_iter()
+        .map(|problem| {
+            if options.echo_output() {
+                println!("Testing {}", problem.branch_name());
+            }
+            test_problem(problem, options, verify)
+        })
+        .collect()
 }
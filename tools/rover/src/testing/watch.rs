@@ -0,0 +1,131 @@
+use crate::{
+    repository::{context::LaunchOptions, problem::Problem, repo::Repository},
+    testing::format::ReportFormat,
+};
+use anyhow::{bail, Context, Result};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, RecvTimeoutError},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+// Editors save a file in several writes, so a run starts once the files are quiet for this long.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+const TESTS_FOLDER: &str = "tests";
+
+type Events = mpsc::Receiver<notify::Result<Event>>;
+
+// The config, the tests and the allowed user files of the problem. The user files are matched
+// when the config is loaded, so a new one is watched after the next change.
+struct WatchedFiles {
+    config: PathBuf,
+    tests: PathBuf,
+    user_files: Vec<PathBuf>,
+}
+
+impl WatchedFiles {
+    fn load(problem: &Problem) -> Self {
+        Self {
+            config: problem.config_path(),
+            tests: problem.path().join(TESTS_FOLDER),
+            user_files: problem
+                .config()
+                .map(|config| config.get_absolute_user_files().to_vec())
+                .unwrap_or_default(),
+        }
+    }
+
+    fn changed(&self, event: Event) -> Option<PathBuf> {
+        if matches!(event.kind, EventKind::Access(_)) {
+            return None;
+        }
+        event.paths.into_iter().find(|path| {
+            *path == self.config || path.starts_with(&self.tests) || self.user_files.contains(path)
+        })
+    }
+}
+
+// Blocks until a watched file changes, and then until no watched file changes for a while.
+fn wait_for_change(events: &Events, watched: &WatchedFiles) -> Result<PathBuf> {
+    let mut changed = loop {
+        let event = events.recv().context("file watcher stopped")?;
+        if let Some(path) = watched.changed(event.context("failed to watch files")?) {
+            break path;
+        }
+    };
+    let mut deadline = Instant::now() + DEBOUNCE;
+    loop {
+        let event = match events.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(event) => event.context("failed to watch files")?,
+            Err(RecvTimeoutError::Timeout) => return Ok(changed),
+            Err(RecvTimeoutError::Disconnected) => bail!("file watcher stopped"),
+        };
+        // The running commands write to the problem too, only watched files delay the run.
+        if let Some(path) = watched.changed(event) {
+            changed = path;
+            deadline = Instant::now() + DEBOUNCE;
+        }
+    }
+}
+
+fn run_steps(problem: &Problem, step: Option<&str>, options: &LaunchOptions) {
+    let outcome = problem.launch_steps(options, step);
+    if options
+        .cancel()
+        .is_some_and(|cancel| cancel.load(Ordering::SeqCst))
+    {
+        return;
+    }
+    let written =
+        outcome.and_then(|outcome| ReportFormat::Human.write(&outcome, &mut io::stdout()));
+    if let Err(err) = written {
+        eprintln!("Error: {err:#}");
+    }
+    println!("Waiting for changes...");
+}
+
+pub fn watch_problem(path: &Path, step: Option<&str>, options: &LaunchOptions) -> Result<()> {
+    let repository = Repository::from_path(path)?;
+    let problem = repository.problem_from_path(path)?;
+    if let Some(name) = step {
+        if !problem
+            .config()?
+            .get_steps()
+            .iter()
+            .any(|step| step.name() == name)
+        {
+            bail!("no step \"{name}\" in {:?}", problem.config_path());
+        }
+    }
+    let (sender, events) = mpsc::channel();
+    let mut watcher =
+        notify::recommended_watcher(sender).context("failed to create file watcher")?;
+    watcher
+        .watch(problem.path(), RecursiveMode::Recursive)
+        .with_context(|| format!("failed to watch {:?}", problem.path()))?;
+
+    let problem = &problem;
+    thread::scope(|scope| {
+        let mut cancel = Arc::new(AtomicBool::new(false));
+        let run_options = options.clone().with_cancel(cancel.clone());
+        let mut run = scope.spawn(move || run_steps(problem, step, &run_options));
+        loop {
+            let changed = wait_for_change(&events, &WatchedFiles::load(problem))?;
+            // A newer change makes the running commands useless.
+            cancel.store(true, Ordering::SeqCst);
+            run.join().expect("watch run panicked");
+            let changed = changed.strip_prefix(problem.path()).unwrap_or(&changed);
+            println!("\nChanged {}, running again", changed.display());
+            cancel = Arc::new(AtomicBool::new(false));
+            let run_options = options.clone().with_cancel(cancel.clone());
+            run = scope.spawn(move || run_steps(problem, step, &run_options));
+        }
+    })
+}
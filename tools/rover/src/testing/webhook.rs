@@ -0,0 +1,52 @@
+use super::report::{check_response, render_template, Reporter, WebhookConfig};
+use crate::repository::outcome::TestOutcome;
+use anyhow::{Context, Result};
+use reqwest::{
+    blocking::Client,
+    header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE},
+    Method,
+};
+
+pub struct WebhookReporter {
+    config: WebhookConfig,
+}
+
+impl WebhookReporter {
+    pub fn new(config: WebhookConfig) -> Self {
+        Self { config }
+    }
+
+    fn headers(&self, outcome: &TestOutcome) -> Result<HeaderMap> {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        for (name, template) in &self.config.headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .with_context(|| format!("invalid header name \"{name}\""))?;
+            let value = HeaderValue::from_str(&render_template(template, outcome)?)
+                .with_context(|| format!("invalid value of header \"{name}\""))?;
+            headers.insert(name, value);
+        }
+        Ok(headers)
+    }
+}
+
+impl Reporter for WebhookReporter {
+    fn push_report(&self, outcomes: &[TestOutcome]) -> Result<()> {
+        let method = Method::from_bytes(self.config.method.to_uppercase().as_bytes())
+            .with_context(|| format!("invalid method \"{}\"", self.config.method))?;
+        let client = Client::new();
+        for outcome in outcomes {
+            let url = render_template(&self.config.url, outcome)?;
+            let headers = self.headers(outcome)?;
+            let body = render_template(&self.config.body, outcome)?;
+            let response = self.config.retry.send(|| {
+                client
+                    .request(method.clone(), &url)
+                    .headers(headers.clone())
+                    .body(body.clone())
+            });
+            check_response(response.with_context(|| format!("send report to {url}"))?)?;
+        }
+        Ok(())
+    }
+}
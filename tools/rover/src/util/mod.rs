@@ -1 +1 @@
-pub(crate) mod util_macro;
+pub mod table;
@@ -0,0 +1,28 @@
+use std::io::{self, Write};
+
+// Writes the rows as columns aligned by the widest cell and separated by " | ".
+pub fn write_table(
+    header: &[String],
+    rows: &[Vec<String>],
+    writer: &mut dyn Write,
+) -> io::Result<()> {
+    let lines = || std::iter::once(header).chain(rows.iter().map(Vec::as_slice));
+    let widths: Vec<usize> = (0..header.len())
+        .map(|column| {
+            lines()
+                .map(|row| row[column].chars().count())
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+    for row in lines() {
+        let line = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:width$}"))
+            .collect::<Vec<_>>()
+            .join(" | ");
+        writeln!(writer, "{}", line.trim_end())?;
+    }
+    Ok(())
+}